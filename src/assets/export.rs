@@ -0,0 +1,245 @@
+// The asset database in formats other tools can read: a Tickompiler include file, JSON and CSV
+
+use std::{collections::HashSet, fmt::Display, str::FromStr};
+
+use anyhow::anyhow;
+use csv::Writer;
+use serde::Serialize;
+use serde_hex::{CompactPfx, SerHexOpt};
+
+use crate::assets::{AssetDatabase, Game};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// `#alias`es for every index, scene and sound ID, to `#include` in Tickflow sources
+    Tickflow,
+    /// Every value of the database, as it's loaded
+    Json,
+    /// One row per game version, remix and game in a remix
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Self::Tickflow => "assets.tickflow",
+            Self::Json => "assets.json",
+            Self::Csv => "assets.csv",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "tickflow" | "tf" => Ok(Self::Tickflow),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            _ => Err(anyhow!(
+                "Unknown export format \"{s}\" (should be tickflow, json or csv)"
+            )),
+        }
+    }
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Tickflow => "tickflow",
+                Self::Json => "json",
+                Self::Csv => "csv",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CsvRow<'a> {
+    #[serde(rename = "Type")]
+    kind: &'static str,
+    #[serde(rename = "Name")]
+    name: &'a str,
+    #[serde(rename = "Version")]
+    version: Option<&'a str>,
+    #[serde(rename = "Scene", with = "SerHexOpt::<CompactPfx>")]
+    scene: Option<u32>,
+    #[serde(rename = "Index", with = "SerHexOpt::<CompactPfx>")]
+    index: Option<u32>,
+    #[serde(rename = "Prologue")]
+    prologue: Option<&'a str>,
+    #[serde(rename = "Cellanim")]
+    cellanim: Option<&'a str>,
+    #[serde(rename = "Layout")]
+    layout: Option<&'a str>,
+    #[serde(rename = "Model")]
+    model: Option<&'a str>,
+    #[serde(rename = "Effect")]
+    effect: Option<&'a str>,
+    #[serde(rename = "AAC")]
+    aac: Option<&'a str>,
+    #[serde(rename = "AAC ID", with = "SerHexOpt::<CompactPfx>")]
+    aac_id: Option<u32>,
+    #[serde(rename = "SFX")]
+    sfx: Option<&'a str>,
+}
+
+/// Name a game goes by in aliases: the shortest prologue of its versions, which is the one
+/// without a number at the end (`agbClap` rather than `agbClap2`)
+fn alias_name(game: &Game) -> String {
+    game.versions
+        .iter()
+        .filter_map(|c| c.prologue.clone())
+        .min_by_key(String::len)
+        .unwrap_or_else(|| game.name.clone())
+}
+
+/// Tickompiler names only take ASCII letters, digits and underscores, so everything else is
+/// dropped. Names that still collide get a `_2`, `_3`... suffix.
+fn push_alias(out: &mut String, used: &mut HashSet<String>, name: &str, value: u32) {
+    let name: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+    let mut unique = name.clone();
+    let mut n = 2;
+    while !used.insert(unique.clone()) {
+        unique = format!("{name}_{n}");
+        n += 1;
+    }
+    *out += &format!("#alias {unique} 0x{value:x}\n");
+}
+
+impl AssetDatabase {
+    pub fn export(&self, format: ExportFormat) -> anyhow::Result<String> {
+        match format {
+            ExportFormat::Tickflow => Ok(self.to_tickflow()),
+            ExportFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+            ExportFormat::Csv => self.to_csv(),
+        }
+    }
+
+    /// Tickompiler include file with an `#alias` for every scene (`scene_ntrFrog`), index
+    /// (`index_ntrFrog2`), AAC ID (`aac_ntrFrog2`) and sound group (`group_ntrShortLive_a`).
+    /// Versions are named by their prologue.
+    pub fn to_tickflow(&self) -> String {
+        let mut out = String::from(
+            "// Game indexes, scenes and sound IDs from Bertram's asset database\n\
+             // Generated with the `export` command, so don't edit it by hand\n",
+        );
+        let mut used = HashSet::new();
+        for game in &self.games {
+            let game_alias = alias_name(game);
+            out += &format!("\n// {}\n", game.name);
+            let name = format!("scene_{game_alias}");
+            push_alias(&mut out, &mut used, &name, game.scene);
+            for version in &game.versions {
+                let alias = version
+                    .prologue
+                    .clone()
+                    .unwrap_or_else(|| format!("{game_alias}_{}", version.key));
+                if let Some(index) = version.index {
+                    push_alias(&mut out, &mut used, &format!("index_{alias}"), index);
+                }
+                if let Some(aac) = &version.aac {
+                    push_alias(&mut out, &mut used, &format!("aac_{alias}"), aac.id);
+                }
+                for group in &version.groups {
+                    let name = format!("group_{alias}_{}", group.key);
+                    push_alias(&mut out, &mut used, &name, group.id);
+                }
+            }
+        }
+        for remix in &self.remixes {
+            let alias = remix.prologue.clone().unwrap_or_else(|| remix.name.clone());
+            out += &format!("\n// {}\n", remix.name);
+            push_alias(&mut out, &mut used, &format!("index_{alias}"), remix.index);
+            if let Some(aac) = &remix.aac {
+                push_alias(&mut out, &mut used, &format!("aac_{alias}"), aac.id);
+            }
+        }
+        out
+    }
+
+    pub fn to_csv(&self) -> anyhow::Result<String> {
+        let mut writer = Writer::from_writer(vec![]);
+        for game in &self.games {
+            for version in &game.versions {
+                writer.serialize(CsvRow {
+                    kind: "game",
+                    name: version.name.as_deref().unwrap_or(&game.name),
+                    version: Some(&version.key),
+                    scene: Some(game.scene),
+                    index: version.index,
+                    prologue: version.prologue.as_deref(),
+                    cellanim: version.cellanim.as_deref(),
+                    layout: version.layout.as_deref(),
+                    model: version.model.as_deref(),
+                    effect: game.effect.as_deref(),
+                    aac: version.aac.as_ref().map(|c| c.name.as_str()),
+                    aac_id: version.aac.as_ref().map(|c| c.id),
+                    sfx: Some(&game.sfx),
+                })?;
+            }
+        }
+        for remix in &self.remixes {
+            writer.serialize(CsvRow {
+                kind: "remix",
+                name: &remix.name,
+                version: None,
+                scene: None,
+                index: Some(remix.index),
+                prologue: remix.prologue.as_deref(),
+                cellanim: None,
+                layout: None,
+                model: None,
+                effect: None,
+                aac: remix.aac.as_ref().map(|c| c.name.as_str()),
+                aac_id: remix.aac.as_ref().map(|c| c.id),
+                sfx: None,
+            })?;
+            for game in &remix.games {
+                writer.serialize(CsvRow {
+                    kind: "remix game",
+                    name: &remix.name,
+                    version: None,
+                    scene: Some(game.scene),
+                    index: None,
+                    prologue: None,
+                    cellanim: game.cellanim.as_deref(),
+                    layout: game.layout.as_deref(),
+                    model: game.model.as_deref(),
+                    effect: game.effect.as_deref(),
+                    aac: None,
+                    aac_id: None,
+                    sfx: Some(&game.sfx),
+                })?;
+            }
+        }
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aliases_are_sanitized_and_unique() {
+        let mut out = String::new();
+        let mut used = HashSet::new();
+        push_alias(&mut out, &mut used, "index_Karate Man (Remix)", 1);
+        push_alias(&mut out, &mut used, "index_KarateMan-Remix", 2);
+        push_alias(&mut out, &mut used, "index_Pokémon", 3);
+        assert_eq!(
+            out,
+            "#alias index_KarateManRemix 0x1\n\
+             #alias index_KarateManRemix_2 0x2\n\
+             #alias index_Pokmon 0x3\n"
+        );
+    }
+}
@@ -0,0 +1,969 @@
+// Megamix's games and remixes, from the TOML files in data/asset
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::assets::romfs::RomfsLayout;
+
+pub mod export;
+pub mod romfs;
+pub mod validate;
+
+/// How close a keyword has to be to a search for it to match, from 0 to 1
+pub const MIN_SIMILARITY: f64 = 0.7;
+
+/// Versions that get listed first, in this order. Any other version comes after them.
+pub const VERSION_ORDER: &[&str] = &["long", "short", "arrange", "endless"];
+
+/// Stream in the sound archive, along with its ID
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "(String, u32)")]
+pub struct Aac {
+    pub name: String,
+    pub id: u32,
+}
+
+impl From<(String, u32)> for Aac {
+    fn from((name, id): (String, u32)) -> Self {
+        Self { name, id }
+    }
+}
+
+/// Wave sound group that's loaded on top of the game's `sfx` group
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SoundGroup {
+    /// Key of the group in the game's file (`a`, `b`...)
+    pub key: String,
+    pub group: String,
+    pub wave: String,
+    pub id: u32,
+}
+
+/// Assets of a game for one of the keys used in its file. Most of them are versions of the game
+/// (`long`, `arrange`...), but some keys only give the assets used in a remix (`remix05`) or in
+/// another mode (`wario`, `practice`), or extra indexes (Coin Toss' `gate00`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GameVersion {
+    pub key: String,
+    /// Name of the version, if it's listed in `[versions]`
+    pub name: Option<String>,
+    /// Whether the key is listed in `extra_indexes`, so it's only an extra index of the game
+    pub extra_index: bool,
+    pub index: Option<u32>,
+    pub prologue: Option<String>,
+    pub cellanim: Option<String>,
+    pub layout: Option<String>,
+    pub model: Option<String>,
+    pub aac: Option<Aac>,
+    pub groups: Vec<SoundGroup>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Game {
+    /// Path of the game's file, as listed in `file.list`
+    pub path: String,
+    pub name: String,
+    pub scene: u32,
+    pub keywords: Vec<String>,
+    pub sfx: String,
+    pub effect: Option<String>,
+    /// Every key used in the game's file, with the ones listed in `[versions]` first
+    pub versions: Vec<GameVersion>,
+    /// Prologue names of the remixes the game shows up in (`remix05`)
+    pub remixes: Vec<String>,
+}
+
+impl Game {
+    /// Versions listed in `[versions]`, that can be played on their own
+    pub fn playable(&self) -> impl Iterator<Item = &GameVersion> {
+        self.versions.iter().filter(|c| c.name.is_some())
+    }
+
+    pub fn version(&self, key: &str) -> Option<&GameVersion> {
+        self.versions.iter().find(|c| c.key == key)
+    }
+}
+
+/// Game as it shows up in a remix, with the assets it uses there
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemixGame {
+    pub scene: u32,
+    pub cellanim: Option<String>,
+    pub layout: Option<String>,
+    pub model: Option<String>,
+    pub effect: Option<String>,
+    pub sfx: String,
+    /// Any other file the game needs in the remix
+    #[serde(default)]
+    pub other: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Remix {
+    /// Path of the remix's file, as listed in `file.list`
+    pub path: String,
+    pub name: String,
+    pub index: u32,
+    pub keywords: Vec<String>,
+    pub prologue: Option<String>,
+    pub aac: Option<Aac>,
+    pub games: Vec<RemixGame>,
+}
+
+/// Either a game or a remix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entry<'a> {
+    Game(&'a Game),
+    Remix(&'a Remix),
+}
+
+impl<'a> Entry<'a> {
+    pub fn name(&self) -> &'a str {
+        match self {
+            Self::Game(c) => &c.name,
+            Self::Remix(c) => &c.name,
+        }
+    }
+
+    pub fn keywords(&self) -> &'a [String] {
+        match self {
+            Self::Game(c) => &c.keywords,
+            Self::Remix(c) => &c.keywords,
+        }
+    }
+}
+
+impl Display for Entry<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// What a game index is used for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexName {
+    /// Name of the game (for this version) or remix
+    pub name: String,
+    /// Version key from the game's file (`long`, `arrange`...), or `remix`
+    pub version: String,
+}
+
+impl Display for IndexName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.version)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetKind {
+    Prologue,
+    Cellanim,
+    Layout,
+    Model,
+    Effect,
+    Aac,
+    SoundGroup,
+    Wave,
+    Sfx,
+    Other,
+}
+
+impl Display for AssetKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Prologue => "prologue",
+                Self::Cellanim => "cellanim",
+                Self::Layout => "layout",
+                Self::Model => "model",
+                Self::Effect => "effect",
+                Self::Aac => "AAC stream",
+                Self::SoundGroup => "sound group",
+                Self::Wave => "wave archive",
+                Self::Sfx => "sound effect group",
+                Self::Other => "file",
+            }
+        )
+    }
+}
+
+/// An asset and the game or remix that uses it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssetUse<'a> {
+    pub entry: Entry<'a>,
+    /// Version of the game that uses it, if it's not used by every version
+    pub version: Option<&'a GameVersion>,
+    /// For remixes, the scene of the game that uses it
+    pub scene: Option<u32>,
+    pub kind: AssetKind,
+    pub name: &'a str,
+    /// Sound ID, for AAC streams and sound groups
+    pub id: Option<u32>,
+}
+
+/// Something in the database that a number could be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueUse<'a> {
+    /// Game index of a game version or a remix
+    Index(Entry<'a>, Option<&'a GameVersion>),
+    /// Scene of a game
+    Scene(&'a Game),
+    /// ID of an AAC stream or a sound group
+    SoundId(AssetUse<'a>),
+}
+
+impl Display for ValueUse<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Index(entry, Some(version)) => write!(
+                f,
+                "Game index of {} ({})",
+                version.name.as_deref().unwrap_or(entry.name()),
+                version.key
+            ),
+            Self::Index(entry, None) => write!(f, "Game index of {entry}"),
+            Self::Scene(game) => write!(f, "Scene of {}", game.name),
+            Self::SoundId(asset) => {
+                write!(
+                    f,
+                    "ID of the {} `{}` in {}",
+                    asset.kind, asset.name, asset.entry
+                )?;
+                if let Some(version) = asset.version {
+                    write!(f, " ({})", version.key)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A game as it shows up in a remix, along with the game itself if its scene is in the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RemixPart<'a> {
+    pub game: Option<&'a Game>,
+    pub assets: &'a RemixGame,
+}
+
+impl RemixPart<'_> {
+    /// Name of the game, or its scene if it isn't in the database
+    pub fn name(&self) -> String {
+        match self.game {
+            Some(c) => c.name.clone(),
+            None => format!("Scene 0x{:x}", self.assets.scene),
+        }
+    }
+}
+
+/// Every game and remix listed in `file.list`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AssetDatabase {
+    /// Folder the database was read from
+    #[serde(skip)]
+    pub path: PathBuf,
+    /// Where the assets are in the RomFS, from `romfs.toml` in the same folder
+    #[serde(skip)]
+    pub romfs: RomfsLayout,
+    pub games: Vec<Game>,
+    pub remixes: Vec<Remix>,
+}
+
+impl AssetDatabase {
+    pub const DEFAULT_PATH: &str = "data/asset";
+    pub const PATH_ENV_VAR: &str = "BERTRAM_ASSETS";
+
+    /// Reads every file in the folder's `file.list`. Remix files are told apart from game files
+    /// by `remix = true`.
+    pub fn from_dir(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let mut out = Self {
+            path: path.to_path_buf(),
+            romfs: RomfsLayout::from_file(path.join(RomfsLayout::FILE_NAME))?,
+            ..Default::default()
+        };
+        for file in read_file_list(path)? {
+            let contents = fs::read_to_string(path.join(&file))
+                .map_err(|e| anyhow!("Couldn't read {file}: {e}"))?;
+            let table: toml::Table =
+                toml::from_str(&contents).map_err(|e| anyhow!("Invalid asset file {file}: {e}"))?;
+            let is_remix = table.get("remix") == Some(&toml::Value::Boolean(true));
+            if is_remix {
+                let remix = toml::Value::Table(table)
+                    .try_into::<RemixFile>()
+                    .map_err(|e| anyhow!("Invalid remix file {file}: {e}"))?;
+                out.remixes.push(remix.into_remix(file)?);
+            } else {
+                let game = toml::Value::Table(table)
+                    .try_into::<GameFile>()
+                    .map_err(|e| anyhow!("Invalid game file {file}: {e}"))?;
+                out.games.push(game.into_game(file)?);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Loads the database from `BERTRAM_ASSETS` if it's set, otherwise from data/asset
+    pub fn from_env() -> anyhow::Result<Self> {
+        Self::from_dir(std::env::var(Self::PATH_ENV_VAR).unwrap_or(Self::DEFAULT_PATH.to_string()))
+    }
+
+    /// Every game and then every remix, in the order they're listed
+    pub fn entries(&self) -> impl Iterator<Item = Entry<'_>> {
+        self.games
+            .iter()
+            .map(Entry::Game)
+            .chain(self.remixes.iter().map(Entry::Remix))
+    }
+
+    /// Game or remix with the given keyword or name, ignoring case
+    pub fn by_keyword(&self, keyword: &str) -> Option<Entry<'_>> {
+        let keyword = keyword.trim();
+        self.entries().find(|c| {
+            c.name().eq_ignore_ascii_case(keyword)
+                || c.keywords().iter().any(|c| c.eq_ignore_ascii_case(keyword))
+        })
+    }
+
+    /// Game or remix whose keywords or name are the closest to `query`, allowing for typos and
+    /// ignoring case, spaces and punctuation
+    pub fn search(&self, query: &str) -> Option<Entry<'_>> {
+        if let Some(c) = self.by_keyword(query) {
+            return Some(c);
+        }
+        let query = normalize_keyword(query);
+        self.entries()
+            .flat_map(|entry| {
+                std::iter::once(entry.name())
+                    .chain(entry.keywords().iter().map(String::as_str))
+                    .map(move |c| (entry, normalize_keyword(c)))
+            })
+            .map(|(entry, keyword)| {
+                (
+                    entry,
+                    strsim::normalized_damerau_levenshtein(&query, &keyword),
+                )
+            })
+            .filter(|(_, similarity)| *similarity >= MIN_SIMILARITY)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(entry, _)| entry)
+    }
+
+    pub fn by_scene(&self, scene: u32) -> Option<&Game> {
+        self.games.iter().find(|c| c.scene == scene)
+    }
+
+    pub fn game_by_prologue(&self, prologue: &str) -> Option<&Game> {
+        self.games.iter().find(|c| {
+            c.versions
+                .iter()
+                .any(|c| c.prologue.as_deref() == Some(prologue))
+        })
+    }
+
+    pub fn remix_by_prologue(&self, prologue: &str) -> Option<&Remix> {
+        self.remixes
+            .iter()
+            .find(|c| c.prologue.as_deref() == Some(prologue))
+    }
+
+    /// Highest scene or game index of any game or remix
+    pub fn highest_scene_or_index(&self) -> u32 {
+        self.games
+            .iter()
+            .flat_map(|c| c.versions.iter().filter_map(|c| c.index).chain([c.scene]))
+            .chain(self.remixes.iter().map(|c| c.index))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Every game version and remix that uses a game index
+    pub fn all_by_index(&self, index: u32) -> Vec<(Entry<'_>, Option<&GameVersion>)> {
+        let mut out = vec![];
+        for game in &self.games {
+            for version in game.versions.iter().filter(|c| c.index == Some(index)) {
+                out.push((Entry::Game(game), Some(version)));
+            }
+        }
+        for remix in self.remixes.iter().filter(|c| c.index == index) {
+            out.push((Entry::Remix(remix), None));
+        }
+        out
+    }
+
+    /// Games of a remix in the order they're listed, with their scenes resolved
+    pub fn composition<'a>(&'a self, remix: &'a Remix) -> Vec<RemixPart<'a>> {
+        remix
+            .games
+            .iter()
+            .map(|c| RemixPart {
+                game: self.by_scene(c.scene),
+                assets: c,
+            })
+            .collect()
+    }
+
+    /// Remixes a game shows up in, going by the `remix` list in its `[versions]`. Remixes that
+    /// aren't in the database are given by their prologue name alone.
+    pub fn remixes_using<'a>(&'a self, game: &'a Game) -> Vec<(&'a str, Option<&'a Remix>)> {
+        game.remixes
+            .iter()
+            .map(|c| (c.as_str(), self.remix_by_prologue(c)))
+            .collect()
+    }
+
+    /// Game version or remix that uses a game index. If more than one does, the first one listed
+    /// is used.
+    pub fn by_index(&self, index: u32) -> Option<(Entry<'_>, Option<&GameVersion>)> {
+        self.all_by_index(index).into_iter().next()
+    }
+
+    /// Name of what uses a game index, as shown next to it in Tickflow
+    pub fn index_name(&self, index: u32) -> Option<IndexName> {
+        let (entry, version) = self.by_index(index)?;
+        Some(match version {
+            Some(version) => IndexName {
+                name: version.name.clone().unwrap_or(entry.name().to_string()),
+                version: version.key.clone(),
+            },
+            None => IndexName {
+                name: entry.name().to_string(),
+                version: "remix".to_string(),
+            },
+        })
+    }
+
+    /// Every asset named in the database, along with what uses it
+    pub fn assets<'a>(&'a self) -> Vec<AssetUse<'a>> {
+        let mut out = vec![];
+        for game in &self.games {
+            let entry = Entry::Game(game);
+            let mut push = |version, kind, name: &'a str, id| {
+                out.push(AssetUse {
+                    entry,
+                    version,
+                    scene: None,
+                    kind,
+                    name,
+                    id,
+                })
+            };
+            push(None, AssetKind::Sfx, &game.sfx, None);
+            if let Some(effect) = &game.effect {
+                push(None, AssetKind::Effect, effect, None);
+            }
+            for version in &game.versions {
+                for (kind, name) in [
+                    (AssetKind::Prologue, &version.prologue),
+                    (AssetKind::Cellanim, &version.cellanim),
+                    (AssetKind::Layout, &version.layout),
+                    (AssetKind::Model, &version.model),
+                ] {
+                    if let Some(name) = name {
+                        push(Some(version), kind, name, None);
+                    }
+                }
+                if let Some(aac) = &version.aac {
+                    push(Some(version), AssetKind::Aac, &aac.name, Some(aac.id));
+                }
+                for group in &version.groups {
+                    push(
+                        Some(version),
+                        AssetKind::SoundGroup,
+                        &group.group,
+                        Some(group.id),
+                    );
+                    push(Some(version), AssetKind::Wave, &group.wave, None);
+                }
+            }
+        }
+        for remix in &self.remixes {
+            let entry = Entry::Remix(remix);
+            let mut push = |scene, kind, name: &'a str, id| {
+                out.push(AssetUse {
+                    entry,
+                    version: None,
+                    scene,
+                    kind,
+                    name,
+                    id,
+                })
+            };
+            if let Some(prologue) = &remix.prologue {
+                push(None, AssetKind::Prologue, prologue, None);
+            }
+            if let Some(aac) = &remix.aac {
+                push(None, AssetKind::Aac, &aac.name, Some(aac.id));
+            }
+            for game in &remix.games {
+                let scene = Some(game.scene);
+                for (kind, name) in [
+                    (AssetKind::Cellanim, &game.cellanim),
+                    (AssetKind::Layout, &game.layout),
+                    (AssetKind::Model, &game.model),
+                    (AssetKind::Effect, &game.effect),
+                ] {
+                    if let Some(name) = name {
+                        push(scene, kind, name, None);
+                    }
+                }
+                push(scene, AssetKind::Sfx, &game.sfx, None);
+                for other in &game.other {
+                    push(scene, AssetKind::Other, other, None);
+                }
+            }
+        }
+        out
+    }
+
+    /// Uses of an asset by its name, ignoring case
+    pub fn by_asset(&self, name: &str) -> Vec<AssetUse<'_>> {
+        self.assets()
+            .into_iter()
+            .filter(|c| c.name.eq_ignore_ascii_case(name))
+            .collect()
+    }
+
+    /// AAC streams and sound groups with the given sound ID
+    pub fn by_sound_id(&self, id: u32) -> Vec<AssetUse<'_>> {
+        self.assets()
+            .into_iter()
+            .filter(|c| c.id == Some(id))
+            .collect()
+    }
+
+    /// Everything a number could be: a game index, a scene or a sound ID
+    pub fn lookup(&self, value: u32) -> Vec<ValueUse<'_>> {
+        let mut out = self
+            .all_by_index(value)
+            .into_iter()
+            .map(|(entry, version)| ValueUse::Index(entry, version))
+            .collect::<Vec<_>>();
+        out.extend(
+            self.games
+                .iter()
+                .filter(|c| c.scene == value)
+                .map(ValueUse::Scene),
+        );
+        out.extend(self.by_sound_id(value).into_iter().map(ValueUse::SoundId));
+        out
+    }
+}
+
+/// Files listed in a folder's `file.list`, skipping comments and empty lines
+pub fn read_file_list(path: &Path) -> anyhow::Result<Vec<String>> {
+    Ok(fs::read_to_string(path.join("file.list"))
+        .map_err(|e| anyhow!("Couldn't read the asset file list: {e}"))?
+        .lines()
+        .map(str::trim)
+        .filter(|c| !c.is_empty() && !c.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+/// Lowercase letters and numbers of a keyword, so "Jumpin' Jazz" and "jumpin jazz" are the same
+fn normalize_keyword(keyword: &str) -> String {
+    keyword
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum VersionValue {
+    Name(String),
+    Remixes(Vec<String>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GameFile {
+    name: String,
+    scene: u32,
+    keywords: Vec<String>,
+    sfx: String,
+    effect: Option<String>,
+    /// Keys in `[indexes]` that aren't versions, but other indexes the game uses
+    #[serde(default)]
+    extra_indexes: Vec<String>,
+    versions: HashMap<String, VersionValue>,
+    #[serde(default)]
+    indexes: HashMap<String, u32>,
+    #[serde(default)]
+    prologue: HashMap<String, String>,
+    #[serde(default)]
+    cellanim: HashMap<String, String>,
+    #[serde(default)]
+    layout: HashMap<String, String>,
+    #[serde(default)]
+    model: HashMap<String, String>,
+    #[serde(default)]
+    aac: HashMap<String, Aac>,
+    #[serde(default)]
+    group: HashMap<String, BTreeMap<String, (String, String, u32)>>,
+}
+
+impl GameFile {
+    fn into_game(mut self, path: String) -> anyhow::Result<Game> {
+        let mut names = HashMap::new();
+        let mut remixes = vec![];
+        for (key, value) in self.versions {
+            match (key.as_str(), value) {
+                ("remix", VersionValue::Remixes(c)) => remixes = c,
+                ("remix", VersionValue::Name(_)) => Err(anyhow!(
+                    "Invalid game file {path}: versions.remix should be a list of remixes"
+                ))?,
+                (_, VersionValue::Name(c)) => {
+                    names.insert(key, c);
+                }
+                (_, VersionValue::Remixes(_)) => Err(anyhow!(
+                    "Invalid game file {path}: versions.{key} should be the name of the version"
+                ))?,
+            }
+        }
+
+        if let Some(key) = self
+            .extra_indexes
+            .iter()
+            .find(|c| !self.indexes.contains_key(*c))
+        {
+            Err(anyhow!(
+                "Invalid game file {path}: extra index `{key}` isn't in [indexes]"
+            ))?
+        }
+
+        let mut keys = names
+            .keys()
+            .chain(self.indexes.keys())
+            .chain(self.prologue.keys())
+            .chain(self.cellanim.keys())
+            .chain(self.layout.keys())
+            .chain(self.model.keys())
+            .chain(self.aac.keys())
+            .chain(self.group.keys())
+            .cloned()
+            .collect::<Vec<_>>();
+        keys.sort_by_key(|c| {
+            (
+                !names.contains_key(c),
+                VERSION_ORDER
+                    .iter()
+                    .position(|d| d == c)
+                    .unwrap_or(VERSION_ORDER.len()),
+                c.clone(),
+            )
+        });
+        keys.dedup();
+
+        let versions = keys
+            .into_iter()
+            .map(|key| GameVersion {
+                name: names.remove(&key),
+                extra_index: self.extra_indexes.contains(&key),
+                index: self.indexes.remove(&key),
+                prologue: self.prologue.remove(&key),
+                cellanim: self.cellanim.remove(&key),
+                layout: self.layout.remove(&key),
+                model: self.model.remove(&key),
+                aac: self.aac.remove(&key),
+                groups: self
+                    .group
+                    .remove(&key)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(key, (group, wave, id))| SoundGroup {
+                        key,
+                        group,
+                        wave,
+                        id,
+                    })
+                    .collect(),
+                key,
+            })
+            .collect();
+
+        Ok(Game {
+            path,
+            name: self.name,
+            scene: self.scene,
+            keywords: self.keywords,
+            sfx: self.sfx,
+            effect: self.effect,
+            versions,
+            remixes,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RemixFile {
+    #[allow(unused)]
+    remix: bool,
+    name: String,
+    index: u32,
+    keywords: Vec<String>,
+    #[serde(default)]
+    prologue: HashMap<String, String>,
+    #[serde(default)]
+    aac: HashMap<String, Aac>,
+    #[serde(default)]
+    game: Vec<RemixGame>,
+}
+
+impl RemixFile {
+    fn into_remix(mut self, path: String) -> anyhow::Result<Remix> {
+        // remixes only have one version, but they use the same tables as games
+        if let Some(key) = self
+            .prologue
+            .keys()
+            .chain(self.aac.keys())
+            .find(|c| *c != "long")
+        {
+            Err(anyhow!(
+                "Invalid remix file {path}: remixes only have a `long` version, found `{key}`"
+            ))?
+        }
+        Ok(Remix {
+            path,
+            name: self.name,
+            index: self.index,
+            keywords: self.keywords,
+            prologue: self.prologue.remove("long"),
+            aac: self.aac.remove("long"),
+            games: self.game,
+        })
+    }
+}
+
+#[cfg(feature = "bot")]
+use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+
+#[cfg(feature = "bot")]
+impl GameVersion {
+    /// Every asset of the version, one per line
+    fn describe(&self) -> String {
+        let mut out = vec![];
+        if let Some(index) = self.index {
+            out.push(format!("Index: `0x{index:x}`"));
+        }
+        for (name, value) in [
+            ("Prologue", &self.prologue),
+            ("Cellanim", &self.cellanim),
+            ("Layout", &self.layout),
+            ("Model", &self.model),
+        ] {
+            if let Some(value) = value {
+                out.push(format!("{name}: `{value}`"));
+            }
+        }
+        if let Some(aac) = &self.aac {
+            out.push(format!("AAC: `{}` (`0x{:08X}`)", aac.name, aac.id));
+        }
+        for group in &self.groups {
+            out.push(format!(
+                "Sound group {}: `{}` (`0x{:08X}`)",
+                group.key, group.group, group.id
+            ));
+        }
+        out.join("\n")
+    }
+}
+
+#[cfg(feature = "bot")]
+impl Entry<'_> {
+    /// Every value of the game or remix. Remixes are named using `db`.
+    pub fn as_serenity_embed(&self, embed: CreateEmbed, db: &AssetDatabase) -> CreateEmbed {
+        let embed = embed
+            .title(self.name())
+            .footer(CreateEmbedFooter::new(format!(
+                "Keywords: {}",
+                self.keywords().join(", ")
+            )));
+        match self {
+            Self::Game(game) => {
+                let mut embed = embed
+                    .field("Scene", format!("`0x{:x}`", game.scene), true)
+                    .field("SFX group", format!("`{}`", game.sfx), true);
+                if let Some(effect) = &game.effect {
+                    embed = embed.field("Effect", format!("`{effect}`"), true);
+                }
+                for version in game.playable() {
+                    embed = embed.field(
+                        format!(
+                            "{} ({})",
+                            version.name.as_deref().unwrap_or_default(),
+                            version.key
+                        ),
+                        version.describe(),
+                        false,
+                    );
+                }
+                let others = game
+                    .versions
+                    .iter()
+                    .filter(|c| c.name.is_none())
+                    .map(|c| format!("**{}**\n{}", c.key, c.describe()))
+                    .collect::<Vec<_>>();
+                if !others.is_empty() {
+                    embed = embed.field("Other assets", others.join("\n"), false);
+                }
+                let remixes = db.remixes_using(game);
+                if !remixes.is_empty() {
+                    embed = embed.field("Remixes", format_remixes(&remixes), false);
+                }
+                embed
+            }
+            Self::Remix(remix) => {
+                let mut embed = embed.field("Index", format!("`0x{:x}`", remix.index), true);
+                if let Some(prologue) = &remix.prologue {
+                    embed = embed.field("Prologue", format!("`{prologue}`"), true);
+                }
+                if let Some(aac) = &remix.aac {
+                    embed =
+                        embed.field("AAC", format!("`{}` (`0x{:08X}`)", aac.name, aac.id), true);
+                }
+                embed.field(
+                    "Games",
+                    db.composition(remix)
+                        .iter()
+                        .map(|c| match &c.assets.cellanim {
+                            Some(cellanim) => format!("- {}: `{cellanim}`", c.name()),
+                            None => format!("- {}", c.name()),
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    false,
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "bot")]
+impl RemixPart<'_> {
+    /// Every asset the game uses in the remix, one per line
+    fn describe(&self) -> String {
+        let mut out = vec![format!("Scene: `0x{:x}`", self.assets.scene)];
+        for (name, value) in [
+            ("Cellanim", &self.assets.cellanim),
+            ("Layout", &self.assets.layout),
+            ("Model", &self.assets.model),
+            ("Effect", &self.assets.effect),
+        ] {
+            if let Some(value) = value {
+                out.push(format!("{name}: `{value}`"));
+            }
+        }
+        out.push(format!("SFX group: `{}`", self.assets.sfx));
+        for other in &self.assets.other {
+            out.push(format!("Other: `{other}`"));
+        }
+        out.join("\n")
+    }
+}
+
+#[cfg(feature = "bot")]
+fn format_remixes(remixes: &[(&str, Option<&Remix>)]) -> String {
+    remixes
+        .iter()
+        .map(|(prologue, remix)| match remix {
+            Some(remix) => format!("- {} (`{prologue}`)", remix.name),
+            None => format!("- `{prologue}` (not in the database)"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(feature = "bot")]
+impl Remix {
+    /// Every game in the remix, along with the assets it uses there
+    pub fn composition_embed(&self, embed: CreateEmbed, db: &AssetDatabase) -> CreateEmbed {
+        let mut embed = embed.title(format!("{} (index 0x{:x})", self.name, self.index));
+        for part in db.composition(self) {
+            embed = embed.field(part.name(), part.describe(), true);
+        }
+        embed
+    }
+}
+
+#[cfg(feature = "bot")]
+impl Game {
+    /// Every remix the game shows up in
+    pub fn remixes_embed(&self, embed: CreateEmbed, db: &AssetDatabase) -> CreateEmbed {
+        let remixes = db.remixes_using(self);
+        embed
+            .title(format!("Remixes with {}", self.name))
+            .description(if remixes.is_empty() {
+                "This game isn't in any remix".to_string()
+            } else {
+                format_remixes(&remixes)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{TempDir, ASSET_FILES};
+
+    fn database(name: &str) -> (TempDir, AssetDatabase) {
+        let dir = TempDir::with_files(name, ASSET_FILES);
+        let db = AssetDatabase::from_dir(dir.path()).unwrap();
+        (dir, db)
+    }
+
+    #[test]
+    fn loads_shipped_database() {
+        let db = AssetDatabase::from_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("data/asset"))
+            .unwrap();
+        assert!(!db.games.is_empty() && !db.remixes.is_empty());
+    }
+
+    #[test]
+    fn loads_games_and_remixes() {
+        let (_dir, db) = database("assets");
+        let game = &db.games[0];
+        assert_eq!(game.path, "frog.toml");
+        assert_eq!(game.remixes, ["remix00"]);
+        let playable = game.playable().map(|c| c.key.as_str()).collect::<Vec<_>>();
+        assert_eq!(playable, ["long", "arrange"]);
+        let long = game.version("long").unwrap();
+        assert_eq!(long.index, Some(0x1));
+        assert_eq!(long.cellanim.as_deref(), Some("ntrFrog_long"));
+        let arrange = game.version("arrange").unwrap();
+        assert_eq!(arrange.prologue.as_deref(), Some("ntrFrog2"));
+        assert_eq!(arrange.cellanim, None);
+
+        let remix = &db.remixes[0];
+        assert_eq!(
+            (remix.index, remix.prologue.as_deref()),
+            (0x60, Some("remix00"))
+        );
+        assert_eq!(db.composition(remix)[0].game, Some(game));
+        assert_eq!(db.highest_scene_or_index(), 0x60);
+    }
+
+    #[test]
+    fn finds_entries() {
+        let (_dir, db) = database("lookup");
+        assert_eq!(db.by_keyword("FROG").map(|c| c.name()), Some("Frog Hop"));
+        assert_eq!(db.search("frgo hop").map(|c| c.name()), Some("Frog Hop"));
+        assert_eq!(db.lookup(0x14).len(), 1);
+        assert!(matches!(
+            db.lookup(0x60)[..],
+            [ValueUse::Index(Entry::Remix(_), None)]
+        ));
+    }
+}
@@ -0,0 +1,123 @@
+// Paths of the files in Megamix's RomFS that each game needs, from data/asset/romfs.toml
+
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::anyhow;
+use serde::Deserialize;
+
+use crate::{
+    assets::{AssetDatabase, AssetKind, Game, GameVersion},
+    crash::saltwater::Region,
+};
+
+/// Path templates for each kind of asset
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AssetPaths {
+    pub cellanim: String,
+    pub layout: String,
+    pub effect: String,
+    pub model: String,
+    pub aac: String,
+}
+
+/// How assets are laid out in the RomFS. Paths are templates, with `{name}` standing for the
+/// asset's name and `{region}` for the region's folder prefix.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RomfsLayout {
+    /// Folder prefix for each region (`US` for `USlayout/`)
+    pub regions: HashMap<String, String>,
+    pub paths: AssetPaths,
+    /// Files with data for every game
+    #[serde(default)]
+    pub shared: Vec<String>,
+}
+
+impl RomfsLayout {
+    pub const FILE_NAME: &str = "romfs.toml";
+
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path.as_ref())
+            .map_err(|e| anyhow!("Couldn't read the RomFS layout: {e}"))?;
+        toml::from_str(&contents).map_err(|e| anyhow!("Invalid RomFS layout: {e}"))
+    }
+
+    fn prefix(&self, region: Region) -> anyhow::Result<&str> {
+        self.regions
+            .iter()
+            .find(|(name, _)| region.matches(name))
+            .map(|(_, prefix)| prefix.as_str())
+            .ok_or(anyhow!("No RomFS folders known for region {region}"))
+    }
+}
+
+/// A file in the RomFS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomfsFile {
+    pub path: String,
+    /// Kind of asset in the file. `None` for shared files, which have to be merged with the
+    /// game's own copy instead of replaced.
+    pub kind: Option<AssetKind>,
+}
+
+impl AssetDatabase {
+    /// Files a version of a game needs in the region's RomFS: its own assets, then the shared
+    /// files it needs entries in
+    pub fn romfs_files(
+        &self,
+        game: &Game,
+        version: &GameVersion,
+        region: Region,
+    ) -> anyhow::Result<Vec<RomfsFile>> {
+        let layout = &self.romfs;
+        let prefix = layout.prefix(region)?;
+        let fill = |template: &str, name: &str| {
+            template.replace("{region}", prefix).replace("{name}", name)
+        };
+
+        let mut out = vec![];
+        for (kind, template, name) in [
+            (
+                AssetKind::Cellanim,
+                &layout.paths.cellanim,
+                version.cellanim.as_ref(),
+            ),
+            (
+                AssetKind::Layout,
+                &layout.paths.layout,
+                version.layout.as_ref(),
+            ),
+            (
+                AssetKind::Model,
+                &layout.paths.model,
+                version.model.as_ref(),
+            ),
+            (
+                AssetKind::Effect,
+                &layout.paths.effect,
+                game.effect.as_ref(),
+            ),
+            (
+                AssetKind::Aac,
+                &layout.paths.aac,
+                version.aac.as_ref().map(|c| &c.name),
+            ),
+        ] {
+            if let Some(name) = name {
+                let path = fill(template, name);
+                if !out.iter().any(|c: &RomfsFile| c.path == path) {
+                    out.push(RomfsFile {
+                        path,
+                        kind: Some(kind),
+                    });
+                }
+            }
+        }
+        out.extend(layout.shared.iter().map(|c| RomfsFile {
+            path: fill(c, ""),
+            kind: None,
+        }));
+        Ok(out)
+    }
+}
@@ -0,0 +1,381 @@
+// Checks for the mistakes that are easy to make when writing the asset files by hand
+
+use std::{collections::BTreeMap, fmt::Display, fs};
+
+use crate::assets::{read_file_list, AssetDatabase, Entry};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// A game lists a remix, or has assets for one, but there's no remix with that prologue
+    UnknownRemix {
+        game: String,
+        remix: String,
+    },
+    /// A remix uses a game whose file doesn't list the remix
+    UnlistedRemix {
+        remix: String,
+        game: String,
+    },
+    DuplicateKeyword {
+        keyword: String,
+        entries: Vec<String>,
+    },
+    DuplicateIndex {
+        index: u32,
+        entries: Vec<String>,
+    },
+    DuplicateScene {
+        scene: u32,
+        games: Vec<String>,
+    },
+    DuplicateAacId {
+        id: u32,
+        entries: Vec<String>,
+    },
+    /// A TOML file in the folder that isn't in `file.list`, so it never gets loaded
+    NotInFileList {
+        path: String,
+    },
+    /// A version key that's in one of `[versions]`, `[indexes]` and `[prologue]` but not in another
+    VersionMismatch {
+        game: String,
+        key: String,
+        missing_from: &'static str,
+    },
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownRemix { game, remix } => {
+                write!(f, "{game} refers to `{remix}`, which isn't a known remix")
+            }
+            Self::UnlistedRemix { remix, game } => {
+                write!(f, "{remix} uses {game}, but {game} doesn't list it")
+            }
+            Self::DuplicateKeyword { keyword, entries } => {
+                write!(f, "Keyword \"{keyword}\" is used by {}", entries.join(", "))
+            }
+            Self::DuplicateIndex { index, entries } => {
+                write!(f, "Index 0x{index:x} is used by {}", entries.join(", "))
+            }
+            Self::DuplicateScene { scene, games } => {
+                write!(f, "Scene 0x{scene:x} is used by {}", games.join(", "))
+            }
+            Self::DuplicateAacId { id, entries } => {
+                write!(f, "AAC ID 0x{id:08X} is used by {}", entries.join(", "))
+            }
+            Self::NotInFileList { path } => write!(f, "{path} isn't in file.list"),
+            Self::VersionMismatch {
+                game,
+                key,
+                missing_from,
+            } => write!(
+                f,
+                "{game}: version `{key}` is missing from [{missing_from}]"
+            ),
+        }
+    }
+}
+
+/// Values that more than one game or remix uses, along with who uses them
+fn duplicates<K: Ord>(values: impl Iterator<Item = (K, String)>) -> Vec<(K, Vec<String>)> {
+    let mut users: BTreeMap<K, Vec<String>> = BTreeMap::new();
+    for (value, user) in values {
+        users.entry(value).or_default().push(user);
+    }
+    users.into_iter().filter(|(_, c)| c.len() > 1).collect()
+}
+
+impl AssetDatabase {
+    /// Checks the database and its folder for inconsistencies. None of them stop it from loading,
+    /// but they're most likely mistakes.
+    pub fn validate(&self) -> anyhow::Result<Vec<Problem>> {
+        let mut out = vec![];
+
+        for game in &self.games {
+            let mut remixes = game.remixes.clone();
+            // assets for a remix are under the remix's prologue name
+            for version in &game.versions {
+                if version.name.is_none()
+                    && version.key.starts_with("remix")
+                    && !remixes.contains(&version.key)
+                {
+                    remixes.push(version.key.clone());
+                }
+            }
+            for remix in remixes {
+                if self.remix_by_prologue(&remix).is_none() {
+                    out.push(Problem::UnknownRemix {
+                        game: game.name.clone(),
+                        remix,
+                    });
+                }
+            }
+        }
+        for remix in &self.remixes {
+            let Some(prologue) = &remix.prologue else {
+                continue;
+            };
+            for game in self.composition(remix).iter().filter_map(|c| c.game) {
+                if !game.remixes.contains(prologue) {
+                    out.push(Problem::UnlistedRemix {
+                        remix: remix.name.clone(),
+                        game: game.name.clone(),
+                    });
+                }
+            }
+        }
+
+        out.extend(
+            duplicates(self.entries().flat_map(|entry| {
+                entry
+                    .keywords()
+                    .iter()
+                    .map(move |c| (c.to_lowercase(), entry.name().to_string()))
+            }))
+            .into_iter()
+            .map(|(keyword, entries)| Problem::DuplicateKeyword { keyword, entries }),
+        );
+        out.extend(
+            duplicates(self.entries().flat_map(|entry| {
+                match entry {
+                    Entry::Game(game) => game
+                        .versions
+                        .iter()
+                        .filter_map(|c| Some((c.index?, format!("{} ({})", game.name, c.key))))
+                        .collect::<Vec<_>>(),
+                    Entry::Remix(remix) => vec![(remix.index, remix.name.clone())],
+                }
+            }))
+            .into_iter()
+            .map(|(index, entries)| Problem::DuplicateIndex { index, entries }),
+        );
+        out.extend(
+            duplicates(self.games.iter().map(|c| (c.scene, c.name.clone())))
+                .into_iter()
+                .map(|(scene, games)| Problem::DuplicateScene { scene, games }),
+        );
+        out.extend(
+            duplicates(self.entries().flat_map(|entry| {
+                match entry {
+                    Entry::Game(game) => game
+                        .versions
+                        .iter()
+                        .filter_map(|c| {
+                            Some((c.aac.as_ref()?.id, format!("{} ({})", game.name, c.key)))
+                        })
+                        .collect::<Vec<_>>(),
+                    Entry::Remix(remix) => remix
+                        .aac
+                        .iter()
+                        .map(|c| (c.id, remix.name.clone()))
+                        .collect(),
+                }
+            }))
+            .into_iter()
+            .map(|(id, entries)| Problem::DuplicateAacId { id, entries }),
+        );
+
+        let listed = read_file_list(&self.path)?;
+        for dir in fs::read_dir(&self.path)? {
+            let dir = dir?;
+            if !dir.file_type()?.is_dir() {
+                continue;
+            }
+            for file in fs::read_dir(dir.path())? {
+                let path = format!(
+                    "{}/{}",
+                    dir.file_name().to_string_lossy(),
+                    file?.file_name().to_string_lossy()
+                );
+                if path.ends_with(".toml") && !listed.contains(&path) {
+                    out.push(Problem::NotInFileList { path });
+                }
+            }
+        }
+
+        for game in &self.games {
+            for version in &game.versions {
+                let tables = [
+                    ("versions", version.name.is_some()),
+                    ("indexes", version.index.is_some()),
+                    ("prologue", version.prologue.is_some()),
+                ];
+                // keys that only have assets for remixes or other modes aren't versions, and
+                // neither are the ones marked as extra indexes (like Coin Toss' gates)
+                if version.extra_index || tables.iter().all(|c| !c.1) {
+                    continue;
+                }
+                for (table, _) in tables.iter().filter(|c| !c.1) {
+                    out.push(Problem::VersionMismatch {
+                        game: game.name.clone(),
+                        key: version.key.clone(),
+                        missing_from: table,
+                    });
+                }
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::test_util::{TempDir, ASSET_FILES};
+
+    /// Problems with the test database, after writing the given files over it
+    fn problems(name: &str, files: &[(&str, &str)]) -> Vec<Problem> {
+        let dir = TempDir::with_files(name, ASSET_FILES);
+        for (path, contents) in files {
+            dir.write(path, contents);
+        }
+        AssetDatabase::from_dir(dir.path())
+            .unwrap()
+            .validate()
+            .unwrap()
+    }
+
+    #[test]
+    fn finds_nothing_wrong_with_consistent_files() {
+        assert_eq!(problems("validate-ok", &[]), []);
+    }
+
+    #[test]
+    fn finds_every_problem() {
+        let found = problems(
+            "validate",
+            &[
+                ("file.list", "frog.toml\nmole.toml\nhop.toml\nremix.toml\n"),
+                (
+                    "mole.toml",
+                    r#"
+                    name = "Mole Dig"
+                    scene = 0x15
+                    keywords = ["mole dig", "frog"]
+                    sfx = "GROUP_MOLE"
+                    extra_indexes = ["bonus"]
+
+                    [versions]
+                    long = "Mole Dig"
+                    endless = "Endless Mole Dig"
+                    remix = ["remix09"]
+
+                    [indexes]
+                    long = 0x60
+                    endless = 0x61
+                    bonus = 0x62
+                    gate = 0x63
+
+                    [prologue]
+                    long = "ntrMole"
+
+                    [aac]
+                    long = ["STRM_MOLE", 0x01000001]
+                    "#,
+                ),
+                (
+                    "hop.toml",
+                    r#"
+                    name = "Hop"
+                    scene = 0x14
+                    keywords = ["hop"]
+                    sfx = "GROUP_HOP"
+                    versions = {}
+                    "#,
+                ),
+                (
+                    "remix.toml",
+                    r#"
+                    remix = true
+                    name = "Honeybee Remix"
+                    index = 0x60
+                    keywords = ["remix00"]
+
+                    [prologue]
+                    long = "remix00"
+
+                    [aac]
+                    long = ["STRM_REMIX00", 0x01000001]
+
+                    [[game]]
+                    scene = 0x14
+                    sfx = "GROUP_FROG"
+
+                    [[game]]
+                    scene = 0x15
+                    sfx = "GROUP_MOLE"
+                    "#,
+                ),
+                ("ntr/stray.toml", "name = \"Stray\""),
+            ],
+        );
+        let expected = [
+            Problem::UnknownRemix {
+                game: "Mole Dig".to_string(),
+                remix: "remix09".to_string(),
+            },
+            Problem::UnlistedRemix {
+                remix: "Honeybee Remix".to_string(),
+                game: "Mole Dig".to_string(),
+            },
+            Problem::DuplicateKeyword {
+                keyword: "frog".to_string(),
+                entries: vec!["Frog Hop".to_string(), "Mole Dig".to_string()],
+            },
+            Problem::DuplicateIndex {
+                index: 0x60,
+                entries: vec!["Mole Dig (long)".to_string(), "Honeybee Remix".to_string()],
+            },
+            Problem::DuplicateScene {
+                scene: 0x14,
+                games: vec!["Frog Hop".to_string(), "Hop".to_string()],
+            },
+            Problem::DuplicateAacId {
+                id: 0x01000001,
+                entries: vec!["Mole Dig (long)".to_string(), "Honeybee Remix".to_string()],
+            },
+            Problem::NotInFileList {
+                path: "ntr/stray.toml".to_string(),
+            },
+            Problem::VersionMismatch {
+                game: "Mole Dig".to_string(),
+                key: "endless".to_string(),
+                missing_from: "prologue",
+            },
+            // `bonus` is an extra index, but `gate` isn't marked as one
+            Problem::VersionMismatch {
+                game: "Mole Dig".to_string(),
+                key: "gate".to_string(),
+                missing_from: "versions",
+            },
+            Problem::VersionMismatch {
+                game: "Mole Dig".to_string(),
+                key: "gate".to_string(),
+                missing_from: "prologue",
+            },
+        ];
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn shipped_database_only_has_known_problems() {
+        let db = AssetDatabase::from_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("data/asset"))
+            .unwrap();
+        let unexpected = db
+            .validate()
+            .unwrap()
+            .into_iter()
+            .filter(|c| match c {
+                Problem::UnknownRemix { remix, .. } => remix != "remix07" && remix != "remixTED",
+                Problem::DuplicateAacId { id, .. } => *id != 0x0100006A,
+                _ => true,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(unexpected, []);
+    }
+}
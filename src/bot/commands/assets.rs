@@ -0,0 +1,185 @@
+use bertram::{
+    assets::{export::ExportFormat, Entry},
+    crash::saltwater::Region,
+};
+use poise::{
+    serenity_prelude::{CreateAttachment, CreateEmbed},
+    CreateReply,
+};
+
+use crate::helpers::embed;
+
+/// Shows the indexes, scenes and asset names of a game or remix
+#[poise::command(prefix_command, category = "For code modders")]
+pub async fn game(
+    ctx: crate::Context<'_>,
+    #[description = "Name or keyword of the game or remix"]
+    #[rest]
+    query: String,
+) -> crate::Result<()> {
+    let db = &ctx.data().assets;
+    let Some(entry) = db.search(&query) else {
+        Err(format!("No game or remix found for \"{query}\""))?
+    };
+    embed(ctx, |e| entry.as_serenity_embed(e, db)).await?;
+    Ok(())
+}
+
+/// Tells whether a hex number is a game index, a scene or a sound ID, and of which game
+#[poise::command(prefix_command, category = "For code modders")]
+pub async fn lookup(
+    ctx: crate::Context<'_>,
+    #[description = "Hex number to look up (e.g. 0x51)"] value: String,
+) -> crate::Result<()> {
+    let digits = match value.get(..2) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("0x") => &value[2..],
+        _ => &value[..],
+    };
+    let Ok(value) = u32::from_str_radix(digits, 16) else {
+        Err("Not a valid hex number")?
+    };
+    let uses = ctx.data().assets.lookup(value);
+    if uses.is_empty() {
+        ctx.say(format!("Nothing in the asset database uses `0x{value:x}`"))
+            .await?;
+        return Ok(());
+    }
+    embed(ctx, |e| {
+        e.title(format!("0x{value:x}")).description(
+            uses.iter()
+                .map(|c| format!("- {c}"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    })
+    .await?;
+    Ok(())
+}
+
+/// Shows the games in a remix, or the remixes a game is in
+#[poise::command(prefix_command, category = "For code modders")]
+pub async fn remix(
+    ctx: crate::Context<'_>,
+    #[description = "Name or keyword of the remix or game"]
+    #[rest]
+    query: String,
+) -> crate::Result<()> {
+    let db = &ctx.data().assets;
+    match db.search(&query) {
+        Some(Entry::Remix(remix)) => embed(ctx, |e| remix.composition_embed(e, db)).await?,
+        Some(Entry::Game(game)) => embed(ctx, |e| game.remixes_embed(e, db)).await?,
+        None => Err(format!("No game or remix found for \"{query}\""))?,
+    };
+    Ok(())
+}
+
+/// Shows where the asset database was loaded from
+#[poise::command(prefix_command, subcommands("verify"), category = "Admin")]
+pub async fn assets(ctx: crate::Context<'_>) -> crate::Result<()> {
+    let db = &ctx.data().assets;
+    embed(ctx, |e| {
+        e.title("Asset database").description(format!(
+            "{} games and {} remixes, from `{}`",
+            db.games.len(),
+            db.remixes.len(),
+            db.path.display()
+        ))
+    })
+    .await?;
+    Ok(())
+}
+
+/// Checks the asset database for mistakes
+#[poise::command(prefix_command, check = "crate::op_check")]
+pub async fn verify(ctx: crate::Context<'_>) -> crate::Result<()> {
+    let problems = ctx.data().assets.validate()?;
+    if problems.is_empty() {
+        embed(ctx, |e| e.title("No problems found in the asset database")).await?;
+        return Ok(());
+    }
+    let text = problems
+        .iter()
+        .map(|c| format!("- {c}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut embed = CreateEmbed::new()
+        .color(crate::BERTRAM_COLOR)
+        .title(format!("{} problems in the asset database", problems.len()));
+    let mut reply = CreateReply::default();
+    if text.len() <= 4000 {
+        embed = embed.description(text);
+    } else {
+        embed = embed.description("Too many to show here, see the attached file");
+        reply = reply.attachment(CreateAttachment::bytes(text, "problems.md"));
+    }
+    ctx.send(reply.embed(embed)).await?;
+    Ok(())
+}
+
+/// Exports the asset database as a Tickflow include file, JSON or CSV
+#[poise::command(prefix_command, category = "For code modders")]
+pub async fn export(
+    ctx: crate::Context<'_>,
+    #[description = "Format to export to: tickflow (default), json or csv"] format: Option<String>,
+) -> crate::Result<()> {
+    let format: ExportFormat = format.as_deref().unwrap_or("tickflow").parse()?;
+    let text = ctx.data().assets.export(format)?;
+    ctx.send(CreateReply::default().attachment(CreateAttachment::bytes(text, format.file_name())))
+        .await?;
+    Ok(())
+}
+
+/// Lists the RomFS files each version of a game needs, for porting or recreating it
+#[poise::command(prefix_command, category = "For code modders")]
+pub async fn romfs(
+    ctx: crate::Context<'_>,
+    #[description = "Region of the copy of Megamix (JP/US/EU/KR)"] region: String,
+    #[description = "Name or keyword of the game"]
+    #[rest]
+    query: String,
+) -> crate::Result<()> {
+    let region: Region = region.parse()?;
+    let db = &ctx.data().assets;
+    let Some(Entry::Game(game)) = db.search(&query) else {
+        Err(format!("No game found for \"{query}\""))?
+    };
+
+    let mut fields = vec![];
+    let mut shared = vec![];
+    for version in game.playable() {
+        let files = db.romfs_files(game, version, region)?;
+        fields.push((
+            format!(
+                "{} ({})",
+                version.name.as_deref().unwrap_or_default(),
+                version.key
+            ),
+            files
+                .iter()
+                .filter_map(|c| Some(format!("- `{}` ({})", c.path, c.kind?)))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            false,
+        ));
+        for file in files.into_iter().filter(|c| c.kind.is_none()) {
+            if !shared.contains(&file.path) {
+                shared.push(file.path);
+            }
+        }
+    }
+    embed(ctx, |e| {
+        e.title(format!("RomFS files for {} ({region})", game.name))
+            .fields(fields)
+            .field(
+                "Shared (add to the existing files, don't replace them)",
+                shared
+                    .iter()
+                    .map(|c| format!("- `{c}`"))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                false,
+            )
+    })
+    .await?;
+    Ok(())
+}
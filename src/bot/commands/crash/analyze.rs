@@ -1,13 +1,18 @@
 use std::{fs::File, io::Cursor};
 
-use anyhow::anyhow;
-
 use bertram::crash::{
     analyze::{self, CrashAnalysis, Symbols},
-    saltwater::Region,
+    diff::SymbolDiff,
+    overlay::SymbolOverlay,
+    saltwater::{Region, SWDVersion},
+    store::VerifyStatus,
+};
+use poise::{
+    serenity_prelude::{CreateAttachment, CreateEmbed},
+    CreateReply,
 };
 
-use crate::helpers::embed;
+use crate::helpers::{embed, fetch_file};
 
 use super::{fetch_luma_dump, fetch_saltwater_dump};
 
@@ -18,24 +23,12 @@ pub async fn symbol(
     #[description = "Location of the symbol (hexadecimal)."] sym: String,
     #[description = "Region to lookup (US/EU/JP/KR). Defaults to US."] region: Option<String>,
 ) -> crate::Result<()> {
-    let region = match region
-        .map(|c| c.to_lowercase())
-        .as_ref()
-        .map(String::as_ref)
-    {
-        Some("us") | None => Region::US,
-        Some("eu") => Region::EU,
-        Some("jp") => Region::JP,
-        Some("kr") => Region::KR,
-        _ => Err(anyhow!("invalid region"))?,
+    let region = match region {
+        Some(c) => c.parse()?,
+        None => Region::US,
     };
 
-    let mut symbols = Symbols::from_paths(
-        format!("sym/rhm.{}.csv", format!("{region:?}").to_lowercase()),
-        "",
-    )?;
-
-    symbols.init_bounds(region)?;
+    let mut symbols = ctx.data().symbols.megamix_symbols(region)?;
 
     let symbol = symbols.find_symbol(u32::from_str_radix(&sym, 16)?)?;
 
@@ -48,6 +41,8 @@ pub async fn symbol(
     Ok(())
 }
 
+/// Analyzes a crash dump. Extra attachments (symbol CSVs or linker maps) are used as symbol
+/// overlays for custom code
 #[poise::command(prefix_command, category = "For code modders")]
 pub async fn analyze(
     ctx: crate::Context<'_>,
@@ -55,16 +50,49 @@ pub async fn analyze(
     link: Option<String>,
 ) -> crate::Result<()> {
     let dump = match fetch_luma_dump(&ctx, link.as_deref()).await {
-        Ok(c) => c.as_generic(Some(5))?,
+        Ok(c) => c.as_generic(Some(5), &ctx.data().symbols)?,
         Err(_) => fetch_saltwater_dump(&ctx, link.as_deref())
             .await?
             .as_generic(),
     };
-    let analysis = CrashAnalysis::from(&dump)?;
+    let overlays = fetch_overlays(&ctx, link.is_none()).await?;
+    let data = ctx.data();
+    let analysis = CrashAnalysis::from_with_overlays(
+        &dump,
+        &data.symbols,
+        &data.rules,
+        &data.assets,
+        overlays,
+    )?;
     embed(ctx, |e| analysis.as_serenity_embed(e)).await?;
     Ok(())
 }
 
+/// Reads the symbol CSVs and linker maps attached to the message as symbol overlays, skipping the
+/// crash dump if it was attached too. Any other attachment (screenshots, Tickflow...) is ignored.
+async fn fetch_overlays(
+    ctx: &crate::Context<'_>,
+    dump_attached: bool,
+) -> crate::Result<Vec<SymbolOverlay>> {
+    let crate::Context::Prefix(c) = ctx else {
+        return Ok(vec![]);
+    };
+    let mut out = vec![];
+    for attachment in c
+        .msg
+        .attachments
+        .iter()
+        .skip(dump_attached as usize)
+        .filter(|c| SymbolOverlay::is_overlay_file(&c.filename))
+    {
+        out.push(SymbolOverlay::from_file(
+            &attachment.filename,
+            &attachment.download().await?,
+        )?);
+    }
+    Ok(out)
+}
+
 /// Generate Saltwater symbols for debug builds
 #[poise::command(prefix_command, category = "Admin", owners_only)]
 pub async fn symbolgen(
@@ -72,22 +100,110 @@ pub async fn symbolgen(
     #[description = "Link to the Saltwater 3GX file. If not provided, it expects the plugin to be sent as an attachment"]
     link: Option<String>,
 ) -> crate::Result<()> {
-    let _3gx = if let crate::Context::Prefix(c) = ctx
-        && !c.msg.attachments.is_empty()
-    {
-        c.msg.attachments[0].download().await?
-    } else {
-        reqwest::get(link.ok_or("No file given")?)
-            .await?
-            .bytes()
-            .await?
-            .into()
+    let _3gx = fetch_file(&ctx, link.as_deref()).await?;
+    let Some(hash) = analyze::get_3gx_commit_hash(&mut Cursor::new(_3gx.as_slice()))? else {
+        Err("Couldn't find the commit hash in the plugin's metadata")?
     };
-    let hash = analyze::get_3gx_commit_hash(&mut Cursor::new(_3gx.as_slice()))?.unwrap();
-    let mut out = File::create(format!("sym/sw._{hash}.csv",))?;
+    let mut out = File::create(ctx.data().symbols.saltwater_debug_path(&hash))?;
 
     Symbols::ctrplugin_symbols_to_csv(&mut Cursor::new(_3gx.as_slice()), &mut out, true)?;
     ctx.say(format!("Wrote symbols for commit {hash}!",))
         .await?;
     Ok(())
 }
+
+/// Compares the symbols of two Saltwater versions (or debug commit hashes)
+#[poise::command(prefix_command, category = "For code modders")]
+pub async fn symdiff(
+    ctx: crate::Context<'_>,
+    #[description = "Older version (e.g. 0.2) or commit hash"] old: String,
+    #[description = "Newer version (e.g. 0.3) or commit hash"] new: String,
+) -> crate::Result<()> {
+    let store = &ctx.data().symbols;
+    let old: SWDVersion = old.parse()?;
+    let new: SWDVersion = new.parse()?;
+    let diff = SymbolDiff::between(
+        &analyze::get_symbol_table(store.saltwater_path(&old)?)?,
+        &analyze::get_symbol_table(store.saltwater_path(&new)?)?,
+    );
+
+    let mut reply = CreateReply::default().embed(
+        CreateEmbed::new()
+            .color(crate::BERTRAM_COLOR)
+            .title(format!("Saltwater {old} → {new}"))
+            .description(if diff.is_empty() {
+                "No symbols changed".to_string()
+            } else {
+                diff.summary()
+            }),
+    );
+    if !diff.is_empty() {
+        reply = reply.attachment(CreateAttachment::bytes(diff.to_string(), "symdiff.txt"));
+    }
+    ctx.send(reply).await?;
+    Ok(())
+}
+
+/// Lists the symbol files Bertram knows about
+#[poise::command(
+    prefix_command,
+    subcommands("list", "verify"),
+    category = "For code modders"
+)]
+pub async fn symbols(ctx: crate::Context<'_>) -> crate::Result<()> {
+    list_inner(ctx).await
+}
+
+/// Lists the symbol files Bertram knows about
+#[poise::command(prefix_command)]
+pub async fn list(ctx: crate::Context<'_>) -> crate::Result<()> {
+    list_inner(ctx).await
+}
+
+async fn list_inner(ctx: crate::Context<'_>) -> crate::Result<()> {
+    let store = &ctx.data().symbols;
+    embed(ctx, |e| {
+        e.title("Symbol files").description(
+            store
+                .list()
+                .iter()
+                .map(|c| {
+                    format!(
+                        "- {}: `{}`{}",
+                        c.kind,
+                        c.path.display(),
+                        if c.path.is_file() { "" } else { " (missing)" }
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    })
+    .await?;
+    Ok(())
+}
+
+/// Checks that every symbol file exists and matches its checksum
+#[poise::command(prefix_command, check = "crate::op_check")]
+pub async fn verify(ctx: crate::Context<'_>) -> crate::Result<()> {
+    let results = ctx.data().symbols.verify()?;
+    let all_ok = results
+        .iter()
+        .all(|c| matches!(c.1, VerifyStatus::Ok | VerifyStatus::Unchecked));
+    embed(ctx, |e| {
+        e.title(if all_ok {
+            "All symbol files are OK"
+        } else {
+            "Some symbol files have problems"
+        })
+        .description(
+            results
+                .iter()
+                .map(|(entry, status)| format!("- {}: {status}", entry.kind))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    })
+    .await?;
+    Ok(())
+}
@@ -40,13 +40,15 @@ pub async fn luma(
         dump.processor,
         dump.exception_type,
         if let ExcType::DataAbort | ExcType::PrefetchAbort = dump.exception_type {
-            let fault = if dump.exception_type == ExcType::DataAbort {
-                let dfsr = dump.registers.get(17).unwrap_or(&0);
-                (dfsr & 0xf) + ((dfsr >> 10) & 1)
+            let status_reg = if dump.exception_type == ExcType::DataAbort {
+                dump.registers.get(17)
             } else {
-                let ifsr = dump.registers.get(18).unwrap_or(&0);
-                ifsr & 0xf
+                dump.registers.get(18)
             };
+            let fault = dump
+                .exception_type
+                .fault_status(*status_reg.unwrap_or(&0))
+                .unwrap_or_default();
             format!(
                 "Fault status: {}\n",
                 FAULT_STATUS_SOURCES
@@ -154,7 +156,7 @@ pub async fn stack(
 
     ctx.say(format!(
         "Stack dump (w/endian) (sp = `{:08x}`):```{}```",
-        dump.registers[13], &formatted_stack
+        dump.registers[13], formatted_stack
     ))
     .await
     .unwrap();
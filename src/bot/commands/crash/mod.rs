@@ -2,34 +2,22 @@ pub mod analyze;
 pub mod luma;
 pub mod saltwater;
 
-pub use analyze::{analyze, symbol, symbolgen};
+pub use analyze::{analyze, symbol, symbolgen, symbols, symdiff};
 pub use luma::{luma, stack};
 pub use saltwater::saltwater;
 
 use std::io::Cursor;
 
-use poise::Context;
-
 use bertram::{
-    crash::{luma::CrashLuma, saltwater::CrashSWD, solve::SolveDiagnosis},
+    crash::{luma::CrashLuma, saltwater::CrashSWD, solve::Explanation},
     ctru::CtruError,
+    tickflow::TickflowBin,
 };
 
-use crate::helpers::embed;
+use crate::helpers::{embed, fetch_file};
 
 async fn fetch_luma_dump(ctx: &crate::Context<'_>, link: Option<&str>) -> crate::Result<CrashLuma> {
-    let file = if let crate::Context::Prefix(c) = ctx
-        && !c.msg.attachments.is_empty()
-    {
-        c.msg.attachments[0].download().await?
-    } else {
-        reqwest::get(link.ok_or("No file given")?)
-            .await?
-            .bytes()
-            .await?
-            .into()
-    };
-
+    let file = fetch_file(ctx, link).await?;
     Ok(CrashLuma::from_file(&mut Cursor::new(file.as_slice()))?)
 }
 
@@ -37,21 +25,35 @@ async fn fetch_saltwater_dump(
     ctx: &crate::Context<'_>,
     link: Option<&str>,
 ) -> crate::Result<CrashSWD> {
-    let file = if let Context::Prefix(c) = ctx
-        && !c.msg.attachments.is_empty()
-    {
-        c.msg.attachments[0].download().await?
-    } else {
-        reqwest::get(link.ok_or("No file given")?)
-            .await?
-            .bytes()
-            .await?
-            .into()
-    };
-
+    let file = fetch_file(ctx, link).await?;
     Ok(CrashSWD::from_file(&mut Cursor::new(file.as_slice()))?)
 }
 
+/// Reads the first attachment that's a Tickflow binary (.bin), skipping the crash dump if it was
+/// attached too
+async fn fetch_tickflow(
+    ctx: &crate::Context<'_>,
+    dump_attached: bool,
+) -> crate::Result<Option<(String, TickflowBin)>> {
+    let crate::Context::Prefix(c) = ctx else {
+        return Ok(None);
+    };
+    let Some(attachment) = c
+        .msg
+        .attachments
+        .iter()
+        .skip(dump_attached as usize)
+        .find(|c| c.filename.ends_with(".bin"))
+    else {
+        return Ok(None);
+    };
+    let file = attachment.download().await?;
+    Ok(Some((
+        attachment.filename.clone(),
+        TickflowBin::from_file(&mut Cursor::new(file.as_slice()))?,
+    )))
+}
+
 /// Analyzes an ErrDisp / ctru error code
 #[poise::command(prefix_command, category = "Helpers")]
 pub async fn ctru(
@@ -66,7 +68,8 @@ pub async fn ctru(
     Ok(())
 }
 
-/// Gives possible errors that could cause a crash
+/// Gives possible errors that could cause a crash. Attach the mod's Tickflow (.bin or C00.bin) to
+/// find out which operation went wrong
 #[poise::command(prefix_command, category = "Helpers")]
 pub async fn solve(
     ctx: crate::Context<'_>,
@@ -74,64 +77,21 @@ pub async fn solve(
     link: Option<String>,
 ) -> crate::Result<()> {
     let dump = match fetch_luma_dump(&ctx, link.as_deref()).await {
-        Ok(c) => c.as_generic(Some(5))?,
+        Ok(c) => c.as_generic(Some(5), &ctx.data().symbols)?,
         Err(_) => fetch_saltwater_dump(&ctx, link.as_deref())
             .await?
             .as_generic(),
     };
-    let diagnoses = SolveDiagnosis::find_matches(&dump)?;
-    let mut output = diagnoses
+    let mut solution = ctx.data().rules.find_matches(&dump, &ctx.data().symbols)?;
+    if let Some((name, bin)) = fetch_tickflow(&ctx, link.is_none()).await? {
+        for diagnosis in &mut solution.diagnoses {
+            diagnosis.pinpoint_tickflow(&dump, &name, &bin);
+        }
+    }
+    let mut output = solution
+        .diagnoses
         .iter()
-        .map(|c| match c {
-            SolveDiagnosis::InvalidTickflowAddress(addr) => (
-                format!(
-                    "Tried to run Tickflow at an invalid address{}",
-                    addr.map(|c| format!(" (`{c:08x}`)")).unwrap_or_default()
-                ),
-                "__100% chance__\n\
-                Things that might cause this:\n\
-                - `call`ing a sub by number\n\
-                - `sub`ing a sub by label\n\
-                - `return`ing on an async thread\n\
-                - Weird Tickompiler behavior (goto loc, or loc shares name with a string)"
-                    .to_string(),
-            ),
-            SolveDiagnosis::NoEffectMemory => (
-                "Ran out of effect file memory (using Karate Man's effect file?)".to_string(),
-                "__~80%__ chance\n\
-                Use Bunny Hop's effect file instead!"
-                    .to_string(),
-            ),
-            //TODO
-            SolveDiagnosis::SceneLoadingError(_err) => (
-                "Error in the scene loading process".to_string(),
-                "Due to call stack size, this diagnosis is unlikely to appear anymore.\n\
-                __~90%__ chance\n\
-                Might be one of the following:\n\
-                - No cellanim/effect/layout loaded\n\
-                - Layout loaded in a slot lesser or equal than 3\n\
-                If this diagnosis gets fixed, more data will later be shown"
-                    .to_string(),
-            ),
-            SolveDiagnosis::NonExecRegion(addr) => (
-                format!("Running code in a non-executable region (`{addr:08x}`)",),
-                "__100%__ chance\n\
-                Something went really wrong with a code patch!"
-                    .to_string(),
-            ),
-            SolveDiagnosis::NullRead => (
-                "Tried to read from null".to_string(),
-                "__100% chance__\n\
-                There's many reasons why this error could be happening, too many to list.\n\
-                However, the most relevant are:\n\
-                - Cellanim/effect/layout not loaded\n\
-                - Layout loaded in a slot lesser or equal than 3\n\
-                - Other scene loading mishaps\n\
-                - A misbehaving code patch\n\
-                - Ran out of memory"
-                    .to_string(),
-            ),
-        })
+        .map(|c| render_explanation(ctx.prefix(), &c.explanation))
         .collect::<Vec<_>>();
 
     if output.is_empty() {
@@ -140,10 +100,12 @@ pub async fn solve(
             "Something really wack is going on - reverse-engineering might be needed.".to_string(),
         ))
     }
+    let mut description = "Here's some possible diagnoses for your error!".to_string();
+    if let Some(stack) = &solution.stack {
+        description += &format!("\nCrashed on the {stack}.");
+    }
     embed(ctx, |e| {
-        let mut e = e
-            .title("Bertram solver")
-            .description("Here's some possible diagnoses for your error!");
+        let mut e = e.title("Bertram solver").description(description);
         for field in output {
             e = e.field(field.0, field.1, false)
         }
@@ -152,3 +114,12 @@ pub async fn solve(
     .await?;
     Ok(())
 }
+
+fn render_explanation(prefix: &str, explanation: &Explanation) -> (String, String) {
+    let mut body = String::new();
+    if let Some(confidence) = explanation.confidence {
+        body += &format!("__{confidence} chance__\n");
+    }
+    body += &explanation.body(|c| format!("`{prefix}faq {}`", c.name));
+    (explanation.title.clone(), body)
+}
@@ -36,13 +36,10 @@ pub async fn saltwater(
         dump.version,
         dump.exception_type,
         if let ExcType::DataAbort | ExcType::PrefetchAbort = dump.exception_type {
-            let fault = if dump.exception_type == ExcType::DataAbort {
-                let dfsr = dump.status_a;
-                (dfsr & 0xf) + ((dfsr >> 10) & 1)
-            } else {
-                let ifsr = dump.status_b;
-                ifsr & 0xf
-            };
+            let fault = dump
+                .exception_type
+                .fault_status(dump.status_a)
+                .unwrap_or_default();
             format!(
                 "Fault status: {}\n",
                 FAULT_STATUS_SOURCES
@@ -1,4 +1,7 @@
 pub mod admin;
+pub mod assets;
 pub mod crash;
 pub mod help;
+pub mod plugin;
 pub mod tags;
+pub mod tickflow;
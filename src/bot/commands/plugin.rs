@@ -0,0 +1,18 @@
+use std::io::Cursor;
+
+use bertram::ctrplugin::Plugin;
+
+use crate::helpers::{embed, fetch_file};
+
+/// Shows the metadata of a 3GX plugin
+#[poise::command(prefix_command, category = "For code modders")]
+pub async fn plugin(
+    ctx: crate::Context<'_>,
+    #[description = "Link to the 3GX file. If not provided, it expects the plugin to be sent as an attachment"]
+    link: Option<String>,
+) -> crate::Result<()> {
+    let file = fetch_file(&ctx, link.as_deref()).await?;
+    let plugin = Plugin::from_file(&mut Cursor::new(file.as_slice()))?;
+    embed(ctx, |e| plugin.as_serenity_embed(e)).await?;
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use std::io::Cursor;
+
+use bertram::tickflow::{
+    conflict::{Conflict, ModTickflow},
+    TickflowBin,
+};
+use poise::{
+    serenity_prelude::{CreateAttachment, CreateEmbed},
+    CreateReply,
+};
+
+use crate::helpers::fetch_file;
+
+/// Longest disassembly that gets sent as a message instead of a file
+const MAX_INLINE_LENGTH: usize = 1900;
+/// Longest report that fits in an embed
+const MAX_EMBED_LENGTH: usize = 4000;
+
+/// Disassembles a Tickflow binary (a Tickompiler .bin or a C00.bin)
+#[poise::command(prefix_command, category = "For code modders")]
+pub async fn disasm(
+    ctx: crate::Context<'_>,
+    #[description = "Link to the .bin file. If not provided, it expects the file to be sent as an attachment"]
+    link: Option<String>,
+) -> crate::Result<()> {
+    let file = fetch_file(&ctx, link.as_deref()).await?;
+    let bin = TickflowBin::from_file(&mut Cursor::new(file.as_slice()))?;
+    let text = bin.disassemble(Some(&ctx.data().assets));
+
+    if text.len() <= MAX_INLINE_LENGTH {
+        ctx.say(format!("```\n{text}```")).await?;
+        return Ok(());
+    }
+    let reply = CreateReply::default()
+        .embed(
+            CreateEmbed::new()
+                .color(crate::BERTRAM_COLOR)
+                .title("Tickflow disassembly")
+                .description(format!("{} operations, {} bytes", bin.ops.len(), bin.size)),
+        )
+        .attachment(CreateAttachment::bytes(text, "disassembly.tickflow"));
+    ctx.send(reply).await?;
+    Ok(())
+}
+
+/// Lists the game indexes replaced by one or more mods, and whether they can be merged into one
+/// C00.bin
+#[poise::command(prefix_command, category = "For code modders")]
+pub async fn mods(
+    ctx: crate::Context<'_>,
+    #[description = "Links to the .bin files, along with the ones sent as attachments"] links: Vec<
+        String,
+    >,
+) -> crate::Result<()> {
+    let mut files = vec![];
+    if let crate::Context::Prefix(c) = ctx {
+        for attachment in &c.msg.attachments {
+            files.push((attachment.filename.clone(), attachment.download().await?));
+        }
+    }
+    for link in links {
+        let name = link.rsplit('/').next().unwrap_or(&link).to_string();
+        files.push((name, reqwest::get(&link).await?.bytes().await?.into()));
+    }
+    if files.is_empty() {
+        Err("No file given")?
+    }
+
+    let mut mods = vec![];
+    for (name, file) in files {
+        let bin = TickflowBin::from_file(&mut Cursor::new(file.as_slice()))
+            .map_err(|e| format!("Couldn't read {name}: {e}"))?;
+        mods.push(ModTickflow { name, bin });
+    }
+    let assets = &ctx.data().assets;
+    let mut text = String::new();
+    for tickflow in &mods {
+        text += &format!("**{}**\n", tickflow.name);
+        for index in tickflow.replaced(Some(assets)) {
+            text += &format!("- {index}\n");
+        }
+    }
+    if mods.len() > 1 {
+        let conflicts = Conflict::find(&mods, Some(assets));
+        if conflicts.is_empty() {
+            text += "\nNo conflicts found, these mods can be merged";
+        } else {
+            text += "\n**Conflicts**\n";
+            for conflict in conflicts {
+                text += &format!("- {conflict}\n");
+            }
+        }
+    }
+
+    let mut embed = CreateEmbed::new()
+        .color(crate::BERTRAM_COLOR)
+        .title("Replaced indexes");
+    let mut reply = CreateReply::default();
+    if text.len() <= MAX_EMBED_LENGTH {
+        embed = embed.description(text);
+    } else {
+        embed = embed.description("Too long to show here, see the attached file");
+        reply = reply.attachment(CreateAttachment::bytes(text, "mods.md"));
+    }
+    ctx.send(reply.embed(embed)).await?;
+    Ok(())
+}
@@ -62,11 +62,7 @@ pub async fn on_error(
             ))
             .await?;
         }
-        FrameworkError::CommandCheckFailed {
-            error: None,
-            ctx: _,
-            ..
-        } => {}
+        FrameworkError::CommandCheckFailed { error: None, .. } => {}
         e => {
             let Some(ctx) = e.ctx() else {
                 println!("{}", e);
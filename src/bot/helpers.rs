@@ -3,7 +3,24 @@ use poise::{serenity_prelude::CreateEmbed, CreateReply};
 pub async fn embed(
     ctx: crate::Context<'_>,
     builder: impl for<'b> FnOnce(CreateEmbed) -> CreateEmbed,
-) -> Result<poise::ReplyHandle, serenity::Error> {
+) -> Result<poise::ReplyHandle<'_>, serenity::Error> {
     ctx.send(CreateReply::default().embed(builder(CreateEmbed::new().color(crate::BERTRAM_COLOR))))
         .await
 }
+
+/// Gets the contents of the first attachment of the message, or downloads `link` if there's none
+pub async fn fetch_file(ctx: &crate::Context<'_>, link: Option<&str>) -> crate::Result<Vec<u8>> {
+    Ok(
+        if let crate::Context::Prefix(c) = ctx
+            && !c.msg.attachments.is_empty()
+        {
+            c.msg.attachments[0].download().await?
+        } else {
+            reqwest::get(link.ok_or("No file given")?)
+                .await?
+                .bytes()
+                .await?
+                .into()
+        },
+    )
+}
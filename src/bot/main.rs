@@ -1,9 +1,11 @@
-#![feature(let_chains, iterator_try_collect)]
-
 use std::collections::HashSet;
 use std::env;
 
 use ::serenity::builder::CreateAllowedMentions;
+use bertram::{
+    assets::AssetDatabase,
+    crash::{rules::RuleSet, store::SymbolStore},
+};
 use poise::{
     serenity_prelude::{self as serenity, UserId},
     Framework, FrameworkError, FrameworkOptions, PrefixFrameworkOptions,
@@ -18,6 +20,9 @@ pub mod helpers;
 pub struct Data {
     pub ops: Vec<UserId>,
     pub prefix_override: Option<String>,
+    pub symbols: SymbolStore,
+    pub rules: RuleSet,
+    pub assets: AssetDatabase,
 }
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Command = poise::Command<Data, Error>;
@@ -64,6 +69,12 @@ async fn op_check(ctx: Context<'_>) -> Result<bool> {
 #[tokio::main]
 async fn main() {
     let prefix_override = std::env::var("BERTRAM_PREFIX").ok();
+    let symbols = SymbolStore::from_env().expect("couldn't open the symbol store");
+    for warning in symbols.warnings() {
+        println!("Warning: {warning}");
+    }
+    let rules = RuleSet::from_env().expect("couldn't load the solver rules");
+    let assets = AssetDatabase::from_env().expect("couldn't load the asset database");
     let framework = Framework::builder()
         .options(FrameworkOptions {
             prefix_options: PrefixFrameworkOptions {
@@ -80,6 +91,8 @@ async fn main() {
                 commands::admin::recompile(),
                 commands::admin::info(),
                 commands::crash::symbolgen(),
+                commands::crash::symbols(),
+                commands::assets::assets(),
                 // crash helpers
                 commands::crash::ctru(),
                 commands::crash::symbol(),
@@ -88,6 +101,16 @@ async fn main() {
                 commands::crash::luma(),
                 commands::crash::saltwater(),
                 commands::crash::analyze(),
+                commands::crash::symdiff(),
+                commands::plugin::plugin(),
+                // tickflow / assets
+                commands::tickflow::disasm(),
+                commands::tickflow::mods(),
+                commands::assets::game(),
+                commands::assets::lookup(),
+                commands::assets::remix(),
+                commands::assets::export(),
+                commands::assets::romfs(),
                 // tags / FAQs
                 commands::tags::docs(),
                 commands::tags::faq(),
@@ -121,6 +144,9 @@ async fn main() {
                 Ok(Data {
                     ops: vec![UserId::new(231520589511262209)],
                     prefix_override,
+                    symbols,
+                    rules,
+                    assets,
                 })
             })
         })
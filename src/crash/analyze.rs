@@ -1,22 +1,27 @@
 use std::{
-    ffi::CString,
+    collections::{HashMap, HashSet},
     fmt::Display,
     fs::File,
-    io::{Read, Seek, SeekFrom, Write},
+    io::{Read, Seek, Write},
     path::Path,
 };
 
 use anyhow::anyhow;
-use bytestream::{ByteOrder::LittleEndian as LE, StreamReader};
 use csv::{DeserializeRecordsIter, Position, Reader, Trim, Writer};
-use grep_matcher::{Captures, Matcher};
-use grep_regex::RegexMatcher;
 use serde::{Deserialize, Serialize};
-use serde_hex::{SerHex, Strict};
+use serde_hex::{SerHex, SerHexOpt, Strict};
 
-use crate::crash::{
-    saltwater::{Region, SWDVersion},
-    CrashInfo, ModdingEngine,
+use crate::{
+    assets::{AssetDatabase, AssetKind, Entry, Game, GameVersion, ValueUse},
+    crash::{
+        dwarf::{DebugInfo, SourceFrame},
+        overlay::SymbolOverlay,
+        rules::RuleSet,
+        saltwater::Region,
+        store::SymbolStore,
+        CrashInfo, ModdingEngine,
+    },
+    ctrplugin::{Plugin, PLUGIN_RANGE},
 };
 
 #[derive(Debug, Clone)]
@@ -25,6 +30,13 @@ pub struct CrashAnalysis {
     pub pc: MaybeFunction,
     pub lr: MaybeFunction,
     pub call_stack: Vec<MaybeFunction>,
+    pub far: Option<DataSymbol>,
+    pub globals: Vec<(String, DataSymbol)>,
+    /// Whether PC, LR or the call stack is in a function that loads a scene
+    pub scene_loading: bool,
+    /// Registers and stack words that look like scenes or game indexes, if a scene was loading
+    pub asset_hints: Vec<AssetHint>,
+    pub warnings: Vec<String>,
 }
 
 impl CrashAnalysis {
@@ -33,11 +45,74 @@ impl CrashAnalysis {
     }
 }
 
+/// A register or stack word whose value is a scene or a game index, along with the assets that go
+/// with it
+#[derive(Debug, Clone)]
+pub struct AssetHint {
+    /// Register (`r1`) or stack word (`sp+0x10`) the value was in
+    pub location: String,
+    pub value: u32,
+    /// What the value is, like "loading scene 0x14 (Frog Hop)"
+    pub meaning: String,
+    /// Cellanims, layouts and effects the scene expects
+    pub assets: Vec<(AssetKind, String)>,
+}
+
+impl Display for AssetHint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({:08x}): {}",
+            self.location, self.value, self.meaning
+        )
+    }
+}
+
+/// Assets a game needs for the given version, or for all of its versions
+fn scene_assets(game: &Game, version: Option<&GameVersion>) -> Vec<(AssetKind, String)> {
+    let versions = match version {
+        Some(c) => vec![c],
+        None => game.playable().collect(),
+    };
+    let mut out = vec![];
+    for version in versions {
+        for (kind, name) in [
+            (AssetKind::Cellanim, &version.cellanim),
+            (AssetKind::Layout, &version.layout),
+        ] {
+            if let Some(name) = name
+                && !out.contains(&(kind, name.clone()))
+            {
+                out.push((kind, name.clone()));
+            }
+        }
+    }
+    if let Some(effect) = &game.effect {
+        out.push((AssetKind::Effect, effect.clone()));
+    }
+    out
+}
+
 #[derive(Debug, Clone)]
 pub struct Function {
     pub reg_pos: u32,
     pub func_pos: u32,
     pub symbol: String,
+    /// Source lines from the plugin's debug info, if there's any
+    pub source: Vec<SourceFrame>,
+}
+
+impl Function {
+    fn format_source(&self, indent: &str) -> String {
+        let mut out = String::new();
+        for (i, frame) in self.source.iter().enumerate() {
+            out += &format!(
+                "{indent}{}{frame}\n",
+                if i == 0 { "" } else { "inlined into " }
+            );
+        }
+        out
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -55,6 +130,56 @@ impl MaybeFunction {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Rodata,
+    Data,
+    Bss,
+    /// Anywhere in a plugin after its code. Plugin symbols don't mark where its .rodata, .data
+    /// and .bss start, so they can't be told apart.
+    NonCode,
+}
+
+impl Display for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Rodata => ".rodata",
+                Self::Data => ".data",
+                Self::Bss => ".bss",
+                Self::NonCode => "non-code",
+            }
+        )
+    }
+}
+
+/// A symbol for a global variable or constant, as opposed to a function
+#[derive(Debug, Clone)]
+pub struct DataSymbol {
+    pub reg_pos: u32,
+    pub sym_pos: u32,
+    pub symbol: String,
+    pub section: Section,
+}
+
+impl DataSymbol {
+    pub fn offset(&self) -> u32 {
+        self.reg_pos - self.sym_pos
+    }
+}
+
+impl Display for DataSymbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.offset() == 0 {
+            write!(f, "{}", self.symbol)
+        } else {
+            write!(f, "{}+0x{:x}", self.symbol, self.offset())
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct CsvSymbol {
     #[serde(alias = "Name")]
@@ -63,6 +188,8 @@ pub struct CsvSymbol {
     pub location: u32,
     #[serde(alias = "Namespace")]
     pub namespace: Option<String>,
+    #[serde(alias = "Size", default, with = "SerHexOpt::<Strict>")]
+    pub size: Option<u32>,
 }
 
 impl CsvSymbol {
@@ -95,37 +222,39 @@ type SymbolIter<'a> = DeserializeRecordsIter<'a, File, CsvSymbol>;
 pub struct Symbols {
     megamix_reader: Reader<File>,
     saltwater_reader: Option<Reader<File>>,
-    megamix_end: Option<u32>,
+    megamix_bounds: Option<CsvBounds>,
     saltwater_end: Option<u32>,
+    saltwater_data_end: Option<u32>,
+    overlays: Vec<SymbolOverlay>,
+    warnings: Vec<String>,
 }
 
+/// Symbols in the linker script that mark the end of a CTRPF plugin's data
+pub(crate) const CTRPLUGIN_END_SYMBOLS: &[&str] = &["__end__", "_end", "__bss_end__"];
+
 pub fn get_3gx_commit_hash(f: &mut (impl Read + Seek)) -> anyhow::Result<Option<String>> {
-    let ref_finder = RegexMatcher::new(r"rev ([0-9a-f]{7})")?;
-    let mut captures = ref_finder.new_captures()?;
-    let mut buf = vec![];
-    f.read_to_end(&mut buf)?;
-    ref_finder.captures(&buf, &mut captures)?;
-    f.seek(SeekFrom::Start(0))?;
-
-    if let Some(a) = captures.get(1) {
-        Ok(Some(
-            String::from_utf8(buf[a.start()..a.end()].to_owned()).unwrap(),
-        ))
-    } else {
-        Ok(None)
-    }
+    Ok(Plugin::from_file(f)?.commit_hash())
 }
 
-pub fn get_megamix_bounds() -> anyhow::Result<Vec<CsvBounds>> {
+pub fn get_megamix_bounds(path: impl AsRef<Path>) -> anyhow::Result<Vec<CsvBounds>> {
     let mut builder = csv::ReaderBuilder::new();
     builder.trim(Trim::Fields);
 
-    let mut megamix_bounds = builder.from_path("sym/bounds.csv")?;
+    let mut megamix_bounds = builder.from_path(path)?;
     Ok(megamix_bounds
         .deserialize::<CsvBounds>()
         .try_collect::<Vec<_>>()?)
 }
 
+/// Reads a whole symbol table into memory
+pub fn get_symbol_table(path: impl AsRef<Path>) -> anyhow::Result<Vec<CsvSymbol>> {
+    let mut builder = csv::ReaderBuilder::new();
+    builder.trim(Trim::Fields);
+
+    let mut table = builder.from_path(path)?;
+    Ok(table.deserialize::<CsvSymbol>().try_collect::<Vec<_>>()?)
+}
+
 impl Symbols {
     pub fn from_paths(
         megamix_path: impl AsRef<Path>,
@@ -142,17 +271,34 @@ impl Symbols {
             } else {
                 Some(builder.from_path(saltwater_path)?)
             },
-            megamix_end: None,
+            megamix_bounds: None,
             saltwater_end: None,
+            saltwater_data_end: None,
+            overlays: vec![],
+            warnings: vec![],
         })
     }
 
-    pub fn megamix(&mut self) -> anyhow::Result<SymbolIter> {
+    /// Adds a note about these symbols that should be shown along with anything using them
+    pub fn warn(&mut self, warning: impl Into<String>) {
+        self.warnings.push(warning.into())
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Adds symbols that take priority over the Megamix and Saltwater ones for the addresses they cover
+    pub fn add_overlay(&mut self, overlay: SymbolOverlay) {
+        self.overlays.push(overlay);
+    }
+
+    pub fn megamix(&mut self) -> anyhow::Result<SymbolIter<'_>> {
         self.megamix_reader.reset()?;
         Ok(self.megamix_reader.deserialize())
     }
 
-    pub fn saltwater(&mut self) -> anyhow::Result<Option<SymbolIter>> {
+    pub fn saltwater(&mut self) -> anyhow::Result<Option<SymbolIter<'_>>> {
         self.saltwater_reader
             .as_mut()
             .map(|c| {
@@ -162,87 +308,149 @@ impl Symbols {
             .unwrap_or(Ok(None))
     }
 
-    pub fn init_bounds(&mut self, region: Region) -> anyhow::Result<()> {
-        let Some(a) = get_megamix_bounds()?
-            .iter()
-            .find(|c| region.matches(&c.version))
-            .cloned()
-        else {
-            Err(anyhow!("Bounds file doesn't include {:?} region", region))?
-        };
-        self.megamix_end = Some(a.rodata);
-
-        self.saltwater_end = if let Some(mut sw_syms) = self.saltwater()? {
-            if let Some(Ok(a)) = sw_syms.find(|c| {
-                if let Ok(c) = c {
-                    c.full_name() == "_TEXT_END"
-                } else {
-                    false
+    /// Sets the bounds of Megamix's sections and finds where the plugin's code and data end. If
+    /// the Saltwater symbols don't mark the end of the data, plugin variables are left unnamed
+    /// (`SymbolStore::warnings` points that out once, instead of every crash doing it).
+    pub fn init_bounds(&mut self, bounds: CsvBounds) -> anyhow::Result<()> {
+        self.megamix_bounds = Some(bounds);
+
+        let mut text_end = None;
+        let mut data_end = None;
+        if let Some(sw_syms) = self.saltwater()? {
+            for sym in sw_syms {
+                let sym = sym?;
+                let name = sym.full_name();
+                if name == "_TEXT_END" {
+                    text_end = Some(sym.location);
+                } else if CTRPLUGIN_END_SYMBOLS.contains(&name.as_str()) {
+                    data_end = Some(sym.location);
                 }
-            }) {
-                Some(a.location)
-            } else {
+            }
+            if text_end.is_none() {
                 Err(anyhow!(
                     "Saltwater symbols file doesn't contain _TEXT_END symbol"
                 ))?
             }
-        } else {
-            None
-        };
+        }
+        self.saltwater_end = text_end;
+        self.saltwater_data_end = data_end;
         Ok(())
     }
 
+    /// Finds the last symbol at or before `pos`, not going further back than `start`
+    fn nearest_symbol(
+        syms: SymbolIter,
+        pos: u32,
+        start: u32,
+    ) -> anyhow::Result<Option<(u32, String)>> {
+        let mut current_sym: Option<(u32, String)> = None;
+        for sym in syms {
+            let sym = sym?;
+            if let Some(c) = &current_sym
+                && sym.location < c.0
+            {
+                unreachable!("This should never happen!")
+            }
+            if sym.location > pos {
+                break;
+            }
+            if sym.location >= start {
+                current_sym = Some((sym.location, sym.full_name()))
+            }
+        }
+        Ok(current_sym)
+    }
+
     pub fn find_symbol(&mut self, pos: u32) -> anyhow::Result<Option<Function>> {
-        let Some(megamix_end) = self.megamix_end else {
+        let Some(bounds) = &self.megamix_bounds else {
             Err(anyhow!("Tried to get a symbol with uninitialized bounds!"))?
         };
 
-        if pos >= 0x00100000 && pos < megamix_end {
-            let mm_syms = self.megamix()?;
-            let mut current_sym: Option<(u32, String)> = None;
-            for sym in mm_syms {
-                let sym = sym?;
-                if let Some(c) = &current_sym
-                    && sym.location < c.0
-                {
-                    unreachable!("This should never happen!")
-                }
-                if sym.location > pos {
-                    break;
-                }
-                current_sym = Some((sym.location, sym.full_name()))
-            }
-            Ok(current_sym.map(|c| Function {
-                reg_pos: pos,
-                func_pos: c.0,
-                symbol: c.1,
-            }))
-        } else if pos >= 0x07000000
+        let current_sym = if let Some(c) = self.overlays.iter().find_map(|c| c.find(pos)) {
+            Some((c.location, c.name.clone()))
+        } else if pos >= bounds.code && pos < bounds.rodata {
+            let code = bounds.code;
+            Self::nearest_symbol(self.megamix()?, pos, code)?
+        } else if PLUGIN_RANGE.contains(&pos)
             && let Some(sw_end) = self.saltwater_end
             && let Some(sw_syms) = self.saltwater()?
             && pos <= sw_end
         {
-            let mut current_sym: Option<(u32, String)> = None;
-            for sym in sw_syms {
+            Self::nearest_symbol(sw_syms, pos, PLUGIN_RANGE.start)?
+        } else {
+            None
+        };
+
+        Ok(current_sym.map(|c| Function {
+            reg_pos: pos,
+            func_pos: c.0,
+            symbol: c.1,
+            source: vec![],
+        }))
+    }
+
+    /// Finds the addresses of the given symbols, first in Megamix and then in Saltwater. If a name
+    /// appears more than once, the first one is used.
+    pub fn find_names(&mut self, names: &HashSet<&str>) -> anyhow::Result<HashMap<String, u32>> {
+        let mut out = HashMap::new();
+        for sym in self.megamix()? {
+            let sym = sym?;
+            let name = sym.full_name();
+            if names.contains(name.as_str()) {
+                out.entry(name).or_insert(sym.location);
+            }
+        }
+        if let Some(syms) = self.saltwater()? {
+            for sym in syms {
                 let sym = sym?;
-                if let Some(c) = &current_sym
-                    && sym.location < c.0
-                {
-                    unreachable!("This should never happen!")
+                let name = sym.full_name();
+                if names.contains(name.as_str()) {
+                    out.entry(name).or_insert(sym.location);
                 }
-                if sym.location > pos {
-                    break;
-                }
-                current_sym = Some((sym.location, sym.full_name()))
             }
-            Ok(current_sym.map(|c| Function {
+        }
+        Ok(out)
+    }
+
+    /// Finds the global variable or constant `pos` points into, if any
+    pub fn find_data_symbol(&mut self, pos: u32) -> anyhow::Result<Option<DataSymbol>> {
+        let Some(bounds) = self.megamix_bounds.clone() else {
+            Err(anyhow!("Tried to get a symbol with uninitialized bounds!"))?
+        };
+
+        let (section, section_start, in_plugin) = if pos >= bounds.rodata && pos < bounds.data {
+            (Section::Rodata, bounds.rodata, false)
+        } else if pos >= bounds.data && pos < bounds.bss_offset {
+            (Section::Data, bounds.data, false)
+        } else if pos >= bounds.bss_offset && pos < bounds.bss_offset + bounds.bss_size {
+            (Section::Bss, bounds.bss_offset, false)
+        } else if let Some(sw_end) = self.saltwater_end
+            && let Some(sw_data_end) = self.saltwater_data_end
+            && pos > sw_end
+            && pos < sw_data_end
+        {
+            (Section::NonCode, sw_end, true)
+        } else {
+            return Ok(None);
+        };
+
+        let syms = if in_plugin {
+            let Some(c) = self.saltwater()? else {
+                return Ok(None);
+            };
+            c
+        } else {
+            self.megamix()?
+        };
+
+        Ok(
+            Self::nearest_symbol(syms, pos, section_start)?.map(|c| DataSymbol {
                 reg_pos: pos,
-                func_pos: c.0,
+                sym_pos: c.0,
                 symbol: c.1,
-            }))
-        } else {
-            Ok(None)
-        }
+                section,
+            }),
+        )
     }
 
     pub fn ctrplugin_symbols_to_csv<F: Read + Seek, W: Write>(
@@ -250,53 +458,20 @@ impl Symbols {
         csv: &mut W,
         demangle: bool,
     ) -> anyhow::Result<()> {
-        let mut magic = [0u8; 8];
-        plg.read_exact(&mut magic)?;
-        if &magic != b"3GX$0002" {
-            Err(anyhow!("not a compatible .3gx file",))?
-        }
-        plg.seek(SeekFrom::Start(0x88))?;
-
-        let num_symbols = u32::read_from(plg, LE)? as u64;
-        let symbols_offset = u32::read_from(plg, LE)? as u64;
-        let name_table = u32::read_from(plg, LE)? as u64;
-
+        let plugin = Plugin::from_file(plg)?;
         let mut writer = Writer::from_writer(csv);
 
-        for i in 0..num_symbols {
-            plg.seek(SeekFrom::Start(symbols_offset + 0xC * i))?;
-
-            let location = u32::read_from(plg, LE)?;
-            plg.seek(SeekFrom::Current(4))?; // size, flags
-            let name_pos = u32::read_from(plg, LE)? as u64;
-
-            plg.seek(SeekFrom::Start(name_table + name_pos))?;
-            let mut name = vec![];
-            loop {
-                let c = u8::read_from(plg, LE)?;
-                if c == 0 {
-                    break;
-                }
-                name.push(c);
-            }
-            let Ok(name) = CString::new(name)?.into_string() else {
-                Err(anyhow!("could not read symbol name"))?
-            };
-
+        for sym in plugin.symbols {
             if demangle {
                 //TODO: demangle symbol names
             }
 
             writer.serialize(CsvSymbol {
-                name: name.clone(),
-                location,
+                name: sym.name,
+                location: sym.address,
                 namespace: None,
+                size: Some(sym.size as u32),
             })?;
-
-            // ctrplugin symbols are sorted, and we don't need data stuff, so just yeet that
-            if name == "_TEXT_END" {
-                break;
-            }
         }
 
         Ok(())
@@ -308,60 +483,57 @@ impl CrashAnalysis {
     const DISPLAY_LR_IF_OOB: bool = false;
     const DISPLAY_CALL_STACK_IF_OOB: bool = true;
 
-    pub fn from(crash: &CrashInfo) -> anyhow::Result<Self> {
-        let region;
-        let mut symbols = match &crash.engine {
-            ModdingEngine::RHMPatch => {
-                region = Region::US;
-                Symbols::from_paths("sym/rhm.us.csv", "")?
-            }
-            ModdingEngine::SpiceRack(_, version, region_) => {
-                region = *region_;
-                Symbols::from_paths(
-                    format!(
-                        "sym/rhm.{}.csv",
-                        match region {
-                            Region::JP => "jp",
-                            Region::US => "us",
-                            Region::EU => "eu",
-                            Region::KR => "kr",
-                            Region::UNK => Err(anyhow!("Cannot analyze unknown region"))?,
-                        }
-                    ),
-                    format!(
-                        "sym/sw.{}.csv",
-                        match version {
-                            SWDVersion::Debug { commit_hash } => "_".to_string() + commit_hash,
-                            SWDVersion::Release {
-                                major,
-                                minor,
-                                patch,
-                            } => format!(
-                                "{major}.{minor}{}",
-                                if *patch != 0 {
-                                    format!(".{patch}")
-                                } else {
-                                    "".to_string()
-                                }
-                            ),
-                        }
-                    ),
-                )?
+    /// Stack words (from SP up) checked for scenes and game indexes. The scene loaders keep them
+    /// in their own frames, so there's no need to go further up.
+    const MAX_HINT_STACK_WORDS: usize = 64;
+    /// Values below this are skipped. Scenes and indexes 0 to 2 do exist (Spaceball, The Clappy
+    /// Trio and Sneaky Spirits), but those values are also what flags, counters and slot numbers
+    /// hold in almost every crash, so hinting at them would mostly be noise. It's a trade-off
+    /// rather than anything from the game: crashes while loading those three scenes get no hints.
+    const MIN_HINT_VALUE: u32 = 3;
+
+    pub fn from(
+        crash: &CrashInfo,
+        store: &SymbolStore,
+        rules: &RuleSet,
+        assets: &AssetDatabase,
+    ) -> anyhow::Result<Self> {
+        Self::from_with_overlays(crash, store, rules, assets, vec![])
+    }
+
+    /// Analyzes a crash, using the given overlays on top of the store's symbols. The rules tell
+    /// which functions load scenes, and the asset database what's in the registers and stack if
+    /// a scene was loading.
+    pub fn from_with_overlays(
+        crash: &CrashInfo,
+        store: &SymbolStore,
+        rules: &RuleSet,
+        assets: &AssetDatabase,
+        overlays: Vec<SymbolOverlay>,
+    ) -> anyhow::Result<Self> {
+        let mut symbols = store.symbols(&crash.engine)?;
+        for overlay in overlays {
+            symbols.add_overlay(overlay);
+        }
+        let debug_info = match store.debug_info(&crash.engine) {
+            Ok(c) => c,
+            Err(e) => {
+                symbols.warn(e.to_string());
+                None
             }
         };
-        symbols.init_bounds(region)?;
 
-        let pc = if let Some(c) = symbols.find_symbol(crash.pc)? {
+        let mut pc = if let Some(c) = symbols.find_symbol(crash.pc)? {
             MaybeFunction::Function(c)
         } else {
             MaybeFunction::Oob(crash.pc)
         };
-        let lr = if let Some(c) = symbols.find_symbol(crash.lr)? {
+        let mut lr = if let Some(c) = symbols.find_symbol(crash.lr)? {
             MaybeFunction::Function(c)
         } else {
             MaybeFunction::Oob(crash.lr)
         };
-        let call_stack = crash
+        let mut call_stack: Vec<MaybeFunction> = crash
             .call_stack
             .as_ref()
             .unwrap_or(&vec![])
@@ -376,15 +548,157 @@ impl CrashAnalysis {
                 })
             })
             .try_collect()?;
+
+        if let Some(debug_info) = &debug_info {
+            let result = Self::add_source(&mut pc, debug_info, false)
+                .and_then(|_| Self::add_source(&mut lr, debug_info, true))
+                .and_then(|_| {
+                    call_stack
+                        .iter_mut()
+                        .try_for_each(|c| Self::add_source(c, debug_info, true))
+                });
+            if let Err(e) = result {
+                symbols.warn(format!("Couldn't read the plugin's debug info: {e}"));
+            }
+        }
+
+        let scene_functions = rules.scene_loading_functions(crash.region(), &mut symbols, store)?;
+        let scene_loading = [&pc, &lr].into_iter().chain(&call_stack).any(
+            |c| matches!(c, MaybeFunction::Function(c) if scene_functions.contains(&c.func_pos)),
+        );
+        let asset_hints = if scene_loading {
+            Self::asset_hints(crash, assets)
+        } else {
+            vec![]
+        };
+
+        let far = match crash.far {
+            Some(c) => symbols.find_data_symbol(c)?,
+            None => None,
+        };
+        let mut globals = vec![];
+        for (i, reg) in crash.r.unwrap_or_default().iter().enumerate() {
+            if let Some(c) = symbols.find_data_symbol(*reg)? {
+                globals.push((format!("r{i}"), c));
+            }
+        }
+
         Ok(Self {
             pc,
             lr,
             call_stack,
+            far,
+            globals,
+            scene_loading,
+            asset_hints,
+            warnings: symbols.warnings().to_vec(),
             ctype: crash.engine.clone(),
         })
     }
 }
 
+impl CrashAnalysis {
+    /// Looks for registers and stack words holding a scene or a game index, and notes which game
+    /// they belong to. Values above every known scene and index (which includes all pointers)
+    /// are skipped, and so are the smallest ones.
+    fn asset_hints(crash: &CrashInfo, db: &AssetDatabase) -> Vec<AssetHint> {
+        let max = db.highest_scene_or_index();
+        let registers = crash
+            .r
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| (format!("r{i}"), c));
+        let stack = crash
+            .stack
+            .as_deref()
+            .unwrap_or_default()
+            .chunks_exact(4)
+            .take(Self::MAX_HINT_STACK_WORDS)
+            .enumerate()
+            .map(|(i, c)| {
+                (
+                    format!("sp+0x{:x}", i * 4),
+                    u32::from_le_bytes(c.try_into().unwrap()),
+                )
+            });
+
+        let mut out = vec![];
+        for (location, value) in registers.chain(stack) {
+            if !(Self::MIN_HINT_VALUE..=max).contains(&value) {
+                continue;
+            }
+            let mut uses = db.lookup(value);
+            uses.sort_by_key(|c| !matches!(c, ValueUse::Scene(_)));
+            for found in uses {
+                let (meaning, assets) = match found {
+                    ValueUse::Scene(game) => (
+                        format!("loading scene 0x{value:x} ({})", game.name),
+                        scene_assets(game, None),
+                    ),
+                    ValueUse::Index(Entry::Game(game), Some(version)) => (
+                        format!(
+                            "game index 0x{value:x} ({}, {})",
+                            version.name.as_deref().unwrap_or(&game.name),
+                            version.key
+                        ),
+                        scene_assets(game, Some(version)),
+                    ),
+                    ValueUse::Index(entry, _) => {
+                        (format!("game index 0x{value:x} ({entry})"), vec![])
+                    }
+                    ValueUse::SoundId(_) => continue,
+                };
+                out.push(AssetHint {
+                    location: location.clone(),
+                    value,
+                    meaning,
+                    assets,
+                });
+            }
+        }
+        out
+    }
+
+    fn add_source(
+        func: &mut MaybeFunction,
+        debug_info: &DebugInfo,
+        return_address: bool,
+    ) -> anyhow::Result<()> {
+        if let MaybeFunction::Function(c) = func
+            && PLUGIN_RANGE.contains(&c.reg_pos)
+        {
+            c.source = debug_info.source_frames(c.reg_pos, return_address)?;
+        }
+        Ok(())
+    }
+
+    fn format_far(&self, indent: &str) -> Option<String> {
+        self.far
+            .as_ref()
+            .map(|c| format!("{indent}far ({:08x}): {c} ({})\n", c.reg_pos, c.section))
+    }
+
+    fn format_globals(&self, indent: &str) -> String {
+        let mut out = String::new();
+        for (reg, c) in &self.globals {
+            out += &format!("{indent}{reg} ({:08x}): {c} ({})\n", c.reg_pos, c.section);
+        }
+        out
+    }
+
+    fn format_asset_hints(&self, indent: &str) -> String {
+        let mut out = String::new();
+        for hint in &self.asset_hints {
+            out += &format!("{indent}{hint}\n");
+            for (kind, name) in &hint.assets {
+                out += &format!("{indent}    {kind}: {name}\n");
+            }
+        }
+        out
+    }
+}
+
 impl Display for CrashAnalysis {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -392,10 +706,12 @@ impl Display for CrashAnalysis {
             concat!(
                 "Crash analysis for {}:\n",
                 "@ {:08x} -> {:08x} (@ PC -> LR)\n\n",
+                "{}",
                 "Call stack:\n",
                 "{}",
                 "{}",
                 "{}",
+                "{}",
             ),
             match &self.ctype {
                 ModdingEngine::RHMPatch => "RHMPatch".to_string(),
@@ -403,10 +719,17 @@ impl Display for CrashAnalysis {
             },
             self.pc.get_raw_pos(),
             self.lr.get_raw_pos(),
+            self.warnings
+                .iter()
+                .map(|c| format!("Warning: {c}\n\n"))
+                .collect::<String>(),
             if let MaybeFunction::Function(c) = &self.pc {
                 format!(
-                    "  PC ({:08x}): {} ({:08x})\n",
-                    c.reg_pos, c.symbol, c.func_pos
+                    "  PC ({:08x}): {} ({:08x})\n{}",
+                    c.reg_pos,
+                    c.symbol,
+                    c.func_pos,
+                    c.format_source("      ")
                 )
             } else if Self::DISPLAY_PC_IF_OOB {
                 format!("  PC ({:08x}): out of bounds!\n", self.pc.get_raw_pos())
@@ -415,8 +738,11 @@ impl Display for CrashAnalysis {
             },
             if let MaybeFunction::Function(c) = &self.lr {
                 format!(
-                    "  LR ({:08x}): {} ({:08x})\n",
-                    c.reg_pos, c.symbol, c.func_pos
+                    "  LR ({:08x}): {} ({:08x})\n{}",
+                    c.reg_pos,
+                    c.symbol,
+                    c.func_pos,
+                    c.format_source("      ")
                 )
             } else if Self::DISPLAY_LR_IF_OOB {
                 format!("  LR ({:08x}): out of bounds!\n", self.lr.get_raw_pos())
@@ -428,11 +754,12 @@ impl Display for CrashAnalysis {
                 for (i, elmt) in self.call_stack.iter().enumerate() {
                     if let MaybeFunction::Function(c) = elmt {
                         out += &format!(
-                            "  Call stack {} ({:08x}): {} ({:08x})\n",
+                            "  Call stack {} ({:08x}): {} ({:08x})\n{}",
                             i + 1,
                             c.reg_pos,
                             c.symbol,
-                            c.func_pos
+                            c.func_pos,
+                            c.format_source("      ")
                         );
                     } else if Self::DISPLAY_CALL_STACK_IF_OOB {
                         out += &format!(
@@ -443,7 +770,25 @@ impl Display for CrashAnalysis {
                     }
                 }
                 out
-            }
+            },
+            match self.format_far("  ") {
+                Some(c) => format!("\nFault address:\n{c}"),
+                None => String::new(),
+            } + &if self.globals.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "\nRegisters pointing to globals:\n{}",
+                    self.format_globals("  ")
+                )
+            } + &if self.asset_hints.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "\nWhile loading a scene:\n{}",
+                    self.format_asset_hints("  ")
+                )
+            },
         )
     }
 }
@@ -454,7 +799,7 @@ use serenity::builder::CreateEmbed;
 #[cfg(feature = "bot")]
 impl CrashAnalysis {
     pub fn as_serenity_embed(&self, embed: CreateEmbed) -> CreateEmbed {
-        embed
+        let mut embed = embed
             .title(format!(
                 "Crash analysis for {}:",
                 match &self.ctype {
@@ -464,9 +809,13 @@ impl CrashAnalysis {
                 }
             ))
             .description(format!(
-                "@ {:08x} -> {:08x} (@ PC -> LR)\n\n",
+                "@ {:08x} -> {:08x} (@ PC -> LR)\n\n{}",
                 self.pc.get_raw_pos(),
-                self.lr.get_raw_pos()
+                self.lr.get_raw_pos(),
+                self.warnings
+                    .iter()
+                    .map(|c| format!("**Warning:** {c}\n"))
+                    .collect::<String>(),
             ))
             .field(
                 "Call stack",
@@ -474,8 +823,11 @@ impl CrashAnalysis {
                     "{}{}{}",
                     if let MaybeFunction::Function(c) = &self.pc {
                         format!(
-                            "PC ({:08x}): {} ({:08x})\n",
-                            c.reg_pos, c.symbol, c.func_pos
+                            "PC ({:08x}): {} ({:08x})\n{}",
+                            c.reg_pos,
+                            c.symbol,
+                            c.func_pos,
+                            c.format_source("    ")
                         )
                     } else if Self::DISPLAY_PC_IF_OOB {
                         format!("PC ({:08x}): out of bounds!\n", self.pc.get_raw_pos())
@@ -484,8 +836,11 @@ impl CrashAnalysis {
                     },
                     if let MaybeFunction::Function(c) = &self.lr {
                         format!(
-                            "LR ({:08x}): {} ({:08x})\n",
-                            c.reg_pos, c.symbol, c.func_pos
+                            "LR ({:08x}): {} ({:08x})\n{}",
+                            c.reg_pos,
+                            c.symbol,
+                            c.func_pos,
+                            c.format_source("    ")
                         )
                     } else if Self::DISPLAY_LR_IF_OOB {
                         format!("LR ({:08x}): out of bounds!\n", self.lr.get_raw_pos())
@@ -497,11 +852,12 @@ impl CrashAnalysis {
                         for (i, elmt) in self.call_stack.iter().enumerate() {
                             if let MaybeFunction::Function(c) = elmt {
                                 out += &format!(
-                                    "Call stack {} ({:08x}): {} ({:08x})\n",
+                                    "Call stack {} ({:08x}): {} ({:08x})\n{}",
                                     i + 1,
                                     c.reg_pos,
                                     c.symbol,
-                                    c.func_pos
+                                    c.func_pos,
+                                    c.format_source("    ")
                                 );
                             } else if Self::DISPLAY_CALL_STACK_IF_OOB {
                                 out += &format!(
@@ -515,7 +871,28 @@ impl CrashAnalysis {
                     }
                 ),
                 false,
-            )
+            );
+        if let Some(c) = self.format_far("") {
+            embed = embed.field("Fault address", c, false);
+        }
+        if !self.globals.is_empty() {
+            embed = embed.field(
+                "Registers pointing to globals",
+                self.format_globals(""),
+                false,
+            );
+        }
+        if !self.asset_hints.is_empty() {
+            embed = embed.field(
+                "While loading a scene",
+                self.format_asset_hints("")
+                    .chars()
+                    .take(1024)
+                    .collect::<String>(),
+                false,
+            );
+        }
+        embed
     }
 }
 
@@ -532,3 +909,140 @@ impl SeekToStart for Reader<File> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{self, TempDir};
+
+    const MEGAMIX: &str = "name,location,namespace
+main,00100000,
+kTable,0039a000,
+gSaveData,00521000,
+gHeap,0054e074,
+";
+    const SALTWATER: &str = "name,location,namespace
+_TEXT_END,07000100,
+gPluginVar,07000200,
+__end__,07000400,
+";
+
+    fn symbols(dir: &TempDir, saltwater: &str) -> Symbols {
+        dir.write("rhm.us.csv", MEGAMIX);
+        dir.write("sw.csv", saltwater);
+        let mut out =
+            Symbols::from_paths(dir.path().join("rhm.us.csv"), dir.path().join("sw.csv")).unwrap();
+        out.init_bounds(test_util::bounds()).unwrap();
+        out
+    }
+
+    fn data_symbol(symbols: &mut Symbols, pos: u32) -> Option<(String, Section)> {
+        symbols
+            .find_data_symbol(pos)
+            .unwrap()
+            .map(|c| (c.to_string(), c.section))
+    }
+
+    #[test]
+    fn finds_data_symbols_by_section() {
+        let dir = TempDir::new("data-symbols");
+        let mut symbols = symbols(&dir, SALTWATER);
+        let found = |name: &str, section| Some((name.to_string(), section));
+
+        assert_eq!(
+            data_symbol(&mut symbols, 0x0039a010),
+            found("kTable+0x10", Section::Rodata)
+        );
+        assert_eq!(
+            data_symbol(&mut symbols, 0x00521014),
+            found("gSaveData+0x14", Section::Data)
+        );
+        assert_eq!(
+            data_symbol(&mut symbols, 0x0054e074),
+            found("gHeap", Section::Bss)
+        );
+        assert_eq!(
+            data_symbol(&mut symbols, 0x07000214),
+            found("gPluginVar+0x14", Section::NonCode)
+        );
+        // code, past the end of .bss, and past the end of the plugin's data
+        assert_eq!(data_symbol(&mut symbols, 0x00100010), None);
+        assert_eq!(data_symbol(&mut symbols, 0x005db2f0), None);
+        assert_eq!(data_symbol(&mut symbols, 0x07000400), None);
+    }
+
+    #[test]
+    fn points_out_scene_being_loaded() {
+        let dir = TempDir::with_files(
+            "scene-hints",
+            &[
+                (
+                    SymbolStore::MANIFEST_NAME,
+                    "bounds.file = \"bounds.csv\"\nregion = [{ region = \"us\", file = \"rhm.us.csv\" }]",
+                ),
+                (
+                    "bounds.csv",
+                    "Version,Code offset,Rodata offset,Data offset,BSS start,BSS size\n\
+                     US,00100000,0039A000,00521000,0054E074,0008D27C\n",
+                ),
+                (
+                    "rhm.us.csv",
+                    "name,location,namespace\nmain,00100000,\nFUN_layout,0020b000,\n",
+                ),
+                (
+                    "rules.toml",
+                    "[[rule]]\nid = \"scene\"\nkind = \"scene_loading_error\"\ntitle = \"Scene\"\n\
+                     when.call_stack_function = { us = 0x0020b494 }",
+                ),
+            ],
+        );
+        let assets_dir = TempDir::with_files("scene-hints-assets", test_util::ASSET_FILES);
+        let store = SymbolStore::new(dir.path()).unwrap();
+        let rules = RuleSet::from_file(dir.path().join("rules.toml")).unwrap();
+        let assets = AssetDatabase::from_dir(assets_dir.path()).unwrap();
+
+        let mut r = [0; 13];
+        r[1] = 0x14;
+        // Frog Hop 2's index, but too small to tell apart from a slot or a flag
+        r[2] = 0x2;
+        let analyze = |pc| {
+            let crash = CrashInfo {
+                pc,
+                r: Some(r),
+                ..test_util::crash()
+            };
+            CrashAnalysis::from(&crash, &store, &rules, &assets).unwrap()
+        };
+
+        let analysis = analyze(0x0020b010);
+        assert!(analysis.scene_loading);
+        let hints = analysis
+            .asset_hints
+            .iter()
+            .map(|c| (c.location.as_str(), c.meaning.as_str(), c.assets.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            hints,
+            [(
+                "r1",
+                "loading scene 0x14 (Frog Hop)",
+                vec![
+                    (AssetKind::Cellanim, "ntrFrog_long".to_string()),
+                    (AssetKind::Effect, "ntrFrog".to_string()),
+                ]
+            )]
+        );
+
+        // outside of the scene loaders, registers aren't looked up
+        let analysis = analyze(0x00100010);
+        assert!(!analysis.scene_loading && analysis.asset_hints.is_empty());
+    }
+
+    #[test]
+    fn leaves_plugin_variables_unnamed_without_data_end() {
+        let dir = TempDir::new("no-data-end");
+        let mut symbols = symbols(&dir, "name,location,namespace\n_TEXT_END,07000100,\n");
+        assert_eq!(data_symbol(&mut symbols, 0x07000214), None);
+        assert!(symbols.warnings().is_empty());
+    }
+}
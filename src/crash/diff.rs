@@ -0,0 +1,262 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::crash::analyze::CsvSymbol;
+
+/// A symbol along with its size, either as given by the symbol table or guessed from the next symbol
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizedSymbol {
+    pub name: String,
+    pub location: u32,
+    pub size: Option<u32>,
+}
+
+impl Display for SizedSymbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} @ 0x{:08x}", self.name, self.location)?;
+        if let Some(size) = self.size {
+            write!(f, " (0x{size:x} bytes)")?;
+        }
+        Ok(())
+    }
+}
+
+/// A value of a symbol (its location or its size) that's different between both tables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolDelta {
+    pub name: String,
+    pub old: u32,
+    pub new: u32,
+}
+
+impl SymbolDelta {
+    pub fn difference(&self) -> i64 {
+        self.new as i64 - self.old as i64
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SymbolDiff {
+    pub added: Vec<SizedSymbol>,
+    pub removed: Vec<SizedSymbol>,
+    pub moved: Vec<SymbolDelta>,
+    pub resized: Vec<SymbolDelta>,
+}
+
+impl SymbolDiff {
+    /// Compares two symbol tables. Symbols are matched by their full name - if a name appears
+    /// more than once in a table, the copies are matched in order of address.
+    pub fn between(old: &[CsvSymbol], new: &[CsvSymbol]) -> Self {
+        let mut old = Self::by_name(old);
+        let new = Self::by_name(new);
+        let mut diff = Self::default();
+
+        for (name, new_syms) in new {
+            let old_syms = old.remove(&name).unwrap_or_default();
+            for (i, new_sym) in new_syms.iter().enumerate() {
+                let Some(old_sym) = old_syms.get(i) else {
+                    diff.added.push(new_sym.clone());
+                    continue;
+                };
+                if old_sym.location != new_sym.location {
+                    diff.moved.push(SymbolDelta {
+                        name: name.clone(),
+                        old: old_sym.location,
+                        new: new_sym.location,
+                    });
+                }
+                if let (Some(old_size), Some(new_size)) = (old_sym.size, new_sym.size)
+                    && old_size != new_size
+                {
+                    diff.resized.push(SymbolDelta {
+                        name: name.clone(),
+                        old: old_size,
+                        new: new_size,
+                    });
+                }
+            }
+            diff.removed
+                .extend(old_syms.into_iter().skip(new_syms.len()));
+        }
+        diff.removed.extend(old.into_values().flatten());
+
+        diff.added.sort_by_key(|c| c.location);
+        diff.removed.sort_by_key(|c| c.location);
+        diff.moved.sort_by_key(|c| c.new);
+        diff.resized.sort_by(|a, b| a.name.cmp(&b.name));
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.moved.is_empty()
+            && self.resized.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} added, {} removed, {} moved, {} resized",
+            self.added.len(),
+            self.removed.len(),
+            self.moved.len(),
+            self.resized.len()
+        )
+    }
+
+    /// Groups a symbol table by name, working out the size of each symbol. If the table doesn't
+    /// have sizes, the distance to the next symbol is used instead.
+    fn by_name(table: &[CsvSymbol]) -> HashMap<String, Vec<SizedSymbol>> {
+        let mut locations = table
+            .iter()
+            .map(|c| c.location)
+            .filter(|c| *c != 0)
+            .collect::<Vec<_>>();
+        locations.sort_unstable();
+        locations.dedup();
+
+        let mut out: HashMap<String, Vec<SizedSymbol>> = HashMap::new();
+        for sym in table {
+            let name = sym.full_name();
+            if name.is_empty() {
+                continue;
+            }
+            let size = sym.size.or_else(|| {
+                if sym.location == 0 {
+                    return None;
+                }
+                let next = locations.partition_point(|c| *c <= sym.location);
+                locations.get(next).map(|c| c - sym.location)
+            });
+            out.entry(name.clone()).or_default().push(SizedSymbol {
+                name,
+                location: sym.location,
+                size,
+            });
+        }
+        for syms in out.values_mut() {
+            syms.sort_by_key(|c| c.location);
+        }
+        out
+    }
+}
+
+impl Display for SymbolDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Symbol diff: {}", self.summary())?;
+        if !self.added.is_empty() {
+            writeln!(f, "\nAdded:")?;
+            for sym in &self.added {
+                writeln!(f, "  + {sym}")?;
+            }
+        }
+        if !self.removed.is_empty() {
+            writeln!(f, "\nRemoved:")?;
+            for sym in &self.removed {
+                writeln!(f, "  - {sym}")?;
+            }
+        }
+        if !self.moved.is_empty() {
+            writeln!(f, "\nMoved:")?;
+            for sym in &self.moved {
+                writeln!(
+                    f,
+                    "  {}: 0x{:08x} -> 0x{:08x} ({})",
+                    sym.name,
+                    sym.old,
+                    sym.new,
+                    signed_hex(sym.difference())
+                )?;
+            }
+        }
+        if !self.resized.is_empty() {
+            writeln!(f, "\nResized:")?;
+            for sym in &self.resized {
+                writeln!(
+                    f,
+                    "  {}: 0x{:x} -> 0x{:x} bytes ({})",
+                    sym.name,
+                    sym.old,
+                    sym.new,
+                    signed_hex(sym.difference())
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn signed_hex(value: i64) -> String {
+    if value < 0 {
+        format!("-0x{:x}", value.unsigned_abs())
+    } else {
+        format!("+0x{value:x}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol(name: &str, location: u32, size: Option<u32>) -> CsvSymbol {
+        CsvSymbol {
+            name: name.to_string(),
+            location,
+            namespace: None,
+            size,
+        }
+    }
+
+    #[test]
+    fn finds_added_removed_moved_and_resized() {
+        let old = [
+            symbol("kept", 0x100, Some(0x10)),
+            symbol("moved", 0x110, Some(0x10)),
+            symbol("resized", 0x120, Some(0x10)),
+            symbol("removed", 0x130, Some(0x10)),
+        ];
+        let new = [
+            symbol("kept", 0x100, Some(0x10)),
+            symbol("resized", 0x120, Some(0x20)),
+            symbol("moved", 0x140, Some(0x10)),
+            symbol("added", 0x150, Some(0x10)),
+        ];
+        let diff = SymbolDiff::between(&old, &new);
+        assert_eq!(diff.summary(), "1 added, 1 removed, 1 moved, 1 resized");
+        assert_eq!(diff.added[0].name, "added");
+        assert_eq!(diff.removed[0].name, "removed");
+        assert_eq!(diff.moved[0].difference(), 0x30);
+        assert_eq!(diff.resized[0].difference(), 0x10);
+        assert!(SymbolDiff::between(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn guesses_sizes_from_the_next_symbol() {
+        let old = [symbol("a", 0x100, None), symbol("b", 0x110, None)];
+        let new = [
+            symbol("a", 0x100, None),
+            symbol("c", 0x108, None),
+            symbol("b", 0x110, None),
+        ];
+        let diff = SymbolDiff::between(&old, &new);
+        assert_eq!(diff.added[0].size, Some(0x8));
+        assert_eq!(
+            diff.resized,
+            [SymbolDelta {
+                name: "a".to_string(),
+                old: 0x10,
+                new: 0x8
+            }]
+        );
+        assert!(diff.moved.is_empty());
+    }
+
+    #[test]
+    fn matches_duplicate_names_in_order() {
+        let old = [symbol("dup", 0x100, Some(4)), symbol("dup", 0x200, Some(4))];
+        let new = [symbol("dup", 0x200, Some(4))];
+        let diff = SymbolDiff::between(&old, &new);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].location, 0x200);
+        assert_eq!(diff.moved[0].old, 0x100);
+    }
+}
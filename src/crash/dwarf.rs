@@ -0,0 +1,77 @@
+use std::{fmt::Display, path::Path};
+
+use addr2line::Loader;
+use anyhow::anyhow;
+
+/// A source location for an address. Inlined functions produce several of these for the same
+/// address, from the innermost function to the one that actually got compiled.
+#[derive(Debug, Clone)]
+pub struct SourceFrame {
+    pub function: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+}
+
+impl Display for SourceFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.function.as_deref().unwrap_or("??"))?;
+        if let Some(file) = &self.file {
+            write!(f, " at {file}")?;
+            if let Some(line) = self.line {
+                write!(f, ":{line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// DWARF debug info from a Saltwater ELF
+pub struct DebugInfo {
+    loader: Loader,
+}
+
+impl DebugInfo {
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        Ok(Self {
+            loader: Loader::new(path)
+                .map_err(|e| anyhow!("Couldn't load debug info from {}: {e}", path.display()))?,
+        })
+    }
+
+    /// Finds the source lines for `pos`, innermost inlined function first.
+    ///
+    /// For return addresses (LR, call stack) use `return_address = true`, so that the line of the
+    /// call itself is looked up instead of whatever comes after it.
+    pub fn source_frames(
+        &self,
+        pos: u32,
+        return_address: bool,
+    ) -> anyhow::Result<Vec<SourceFrame>> {
+        let mut probe = pos & !1;
+        if return_address {
+            probe = probe.saturating_sub(1);
+        }
+
+        let mut frames = self
+            .loader
+            .find_frames(probe as u64)
+            .map_err(|e| anyhow!("Couldn't read debug info: {e}"))?;
+        let mut out = vec![];
+        while let Some(frame) = frames.next()? {
+            out.push(SourceFrame {
+                function: match &frame.function {
+                    Some(c) => Some(c.demangle()?.into_owned()),
+                    None => None,
+                },
+                file: frame
+                    .location
+                    .as_ref()
+                    .and_then(|c| c.file)
+                    .map(String::from),
+                line: frame.location.as_ref().and_then(|c| c.line),
+            });
+        }
+        Ok(out)
+    }
+}
@@ -3,11 +3,9 @@ use std::{
     io::{Read, Seek, SeekFrom},
 };
 
+use crate::crash::{store::SymbolStore, CrashInfo, ExcType};
 use anyhow::anyhow;
 use bytestream::{ByteOrder::LittleEndian as LE, StreamReader};
-use csv::Trim;
-
-use crate::crash::{CrashInfo, ExcType};
 
 use super::{analyze::CsvBounds, saltwater::Region};
 
@@ -137,14 +135,19 @@ impl CrashLuma {
         })
     }
 
-    pub fn as_generic(self, call_stack_size: Option<usize>) -> anyhow::Result<CrashInfo> {
+    pub fn as_generic(
+        self,
+        call_stack_size: Option<usize>,
+        store: &SymbolStore,
+    ) -> anyhow::Result<CrashInfo> {
         let r = Some(self.registers[0..13].try_into().unwrap());
         Ok(CrashInfo {
             call_stack: match call_stack_size {
                 None | Some(0) => None,
-                Some(c) => Some(self.get_call_stack(c)?),
+                Some(c) => Some(self.get_call_stack(c, &store.region_bounds(Region::US)?)),
             },
             engine: super::ModdingEngine::RHMPatch,
+            exception_type: self.exception_type,
             r,
             sp: Some(self.registers[13]),
             lr: self.registers[14],
@@ -160,29 +163,17 @@ impl CrashLuma {
         })
     }
 
-    pub fn get_call_stack(&self, size: usize) -> anyhow::Result<Vec<u32>> {
+    pub fn get_call_stack(&self, size: usize, bounds: &CsvBounds) -> Vec<u32> {
         let mut call_stack = vec![];
         let mut i = 0;
-        while i < self.stack.len() && call_stack.len() < size {
+        while i + 4 <= self.stack.len() && call_stack.len() < size {
             let val = u32::from_le_bytes(self.stack[i..i + 4].try_into().unwrap());
-
-            let mut builder = csv::ReaderBuilder::new();
-            builder.trim(Trim::Fields);
-
-            let mut megamix_bounds = builder.from_path("sym/bounds.csv")?;
-            let Some(Ok(a)) = megamix_bounds.deserialize::<CsvBounds>().find(|c| {
-                let Ok(bound) = c else { return false };
-                Region::US.matches(&bound.version)
-            }) else {
-                Err(anyhow!("Bounds file doesn't include US region"))?
-            };
-
-            if (0x00100000..a.rodata).contains(&val) {
+            if (bounds.code..bounds.rodata).contains(&val) {
                 call_stack.push(val);
             }
             i += 4;
         }
-        Ok(call_stack)
+        call_stack
     }
 
     pub fn get_title_info(&self) -> Option<(String, u64)> {
@@ -1,11 +1,21 @@
 use std::fmt::Display;
 
+use serde::Deserialize;
+
+use crate::{crash::analyze::CsvBounds, ctrplugin::PLUGIN_RANGE};
+
 pub mod analyze;
+pub mod diff;
+pub mod dwarf;
 pub mod luma;
+pub mod overlay;
+pub mod rules;
 pub mod saltwater;
 pub mod solve;
+pub mod store;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ExcType {
     FloatingPoint,
     UndefinedInst,
@@ -59,6 +69,15 @@ impl ExcType {
         }
     }
 
+    /// Fault status source (as listed in `FAULT_STATUS_SOURCES`) from the DFSR or IFSR
+    pub const fn fault_status(&self, status_reg: u32) -> Option<u32> {
+        match self {
+            Self::DataAbort => Some((status_reg & 0xf) | ((status_reg >> 10) & 1) << 4),
+            Self::PrefetchAbort => Some(status_reg & 0xf),
+            _ => None,
+        }
+    }
+
     pub const fn from_errf_code(errf: u8) -> Option<Self> {
         match errf {
             0 => Some(Self::PrefetchAbort),
@@ -88,6 +107,7 @@ impl ModdingEngine {
 #[derive(Debug, Clone)]
 pub struct CrashInfo {
     pub engine: ModdingEngine,
+    pub exception_type: ExcType,
 
     pub r: Option<[u32; 13]>,
     pub sp: Option<u32>,
@@ -110,8 +130,68 @@ impl CrashInfo {
     pub fn region(&self) -> saltwater::Region {
         self.engine.region()
     }
+
+    /// Fault status source of a data or prefetch abort, as listed in `FAULT_STATUS_SOURCES`
+    pub fn fault_status(&self) -> Option<u32> {
+        let status_reg = match self.exception_type {
+            ExcType::DataAbort => self.dfsr?,
+            ExcType::PrefetchAbort => self.ifsr?,
+            _ => None?,
+        };
+        self.exception_type.fault_status(status_reg)
+    }
+
+    /// Whether the CPU was in Thumb state (CPSR T bit)
+    pub fn is_thumb(&self) -> bool {
+        self.cpsr & CPSR_THUMB != 0
+    }
+
+    /// If the crash looks like it came from branching into Thumb code in ARM state (or the other
+    /// way round), the address that got branched to. After such a branch the CPU runs whatever
+    /// garbage the code decodes to, so that's about where PC ends up.
+    ///
+    /// - ARM state with a PC that isn't word-aligned can only be Thumb code
+    /// - Thumb state, coming from ARM code (LR without its low bit), ending in an undefined
+    ///   instruction or prefetch abort inside code is most likely ARM code
+    pub fn interworking_mismatch(&self, bounds: &CsvBounds) -> Option<u32> {
+        if !self.is_thumb() {
+            return (self.pc & 3 != 0).then_some(self.pc);
+        }
+        let in_code = (bounds.code..bounds.rodata).contains(&self.pc)
+            || matches!(self.engine, ModdingEngine::SpiceRack(..))
+                && PLUGIN_RANGE.contains(&self.pc);
+        (in_code
+            && self.lr & 1 == 0
+            && matches!(
+                self.exception_type,
+                ExcType::UndefinedInst | ExcType::PrefetchAbort
+            ))
+        .then_some(self.pc)
+    }
+
+    /// The call stack, followed by every other value in the stack dump that points into code.
+    /// Those are mostly return addresses, so this works as a deeper (but noisier) call stack.
+    pub fn deep_call_stack(&self, bounds: &CsvBounds) -> Vec<u32> {
+        let mut out = self.call_stack.clone().unwrap_or_default();
+        for word in self.stack.as_deref().unwrap_or_default().chunks_exact(4) {
+            let val = u32::from_le_bytes(word.try_into().unwrap());
+            let is_code = (bounds.code..bounds.rodata).contains(&val)
+                || matches!(self.engine, ModdingEngine::SpiceRack(..))
+                    && PLUGIN_RANGE.contains(&val);
+            if is_code && !out.contains(&val) {
+                out.push(val);
+            }
+        }
+        out
+    }
 }
 
+/// Thumb state bit of the CPSR
+pub const CPSR_THUMB: u32 = 1 << 5;
+
+/// Fault status source for alignment faults
+pub const FAULT_STATUS_ALIGNMENT: u32 = 0b1;
+
 pub const FAULT_STATUS_SOURCES: &[(u32, &str)] = &[
     (0b1, "Alignment"),
     (0b100, "Instruction cache maintenance operation fault"),
@@ -129,3 +209,78 @@ pub const FAULT_STATUS_SOURCES: &[(u32, &str)] = &[
     (0b10110, "Imprecise External Abort"),
     (0b10, "Debug event"),
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    fn crash(exception_type: ExcType, cpsr: u32, pc: u32, lr: u32) -> CrashInfo {
+        CrashInfo {
+            exception_type,
+            cpsr,
+            pc,
+            lr,
+            ..test_util::crash()
+        }
+    }
+
+    #[test]
+    fn finds_thumb_code_run_in_arm_state() {
+        let bounds = test_util::bounds();
+        let unaligned = crash(ExcType::UndefinedInst, 0x10, 0x00200002, 0x00100001);
+        assert_eq!(unaligned.interworking_mismatch(&bounds), Some(0x00200002));
+        let aligned = crash(ExcType::UndefinedInst, 0x10, 0x00200004, 0x00100001);
+        assert_eq!(aligned.interworking_mismatch(&bounds), None);
+    }
+
+    #[test]
+    fn finds_arm_code_run_in_thumb_state() {
+        let bounds = test_util::bounds();
+        let thumb = 0x10 | CPSR_THUMB;
+        for exception in [ExcType::UndefinedInst, ExcType::PrefetchAbort] {
+            let from_arm = crash(exception, thumb, 0x00200006, 0x00100004);
+            assert_eq!(from_arm.interworking_mismatch(&bounds), Some(0x00200006));
+        }
+        // coming from Thumb code, outside of code, or crashing on a data access
+        let from_thumb = crash(ExcType::UndefinedInst, thumb, 0x00200006, 0x00100005);
+        assert_eq!(from_thumb.interworking_mismatch(&bounds), None);
+        let outside_code = crash(ExcType::UndefinedInst, thumb, 0x00521006, 0x00100004);
+        assert_eq!(outside_code.interworking_mismatch(&bounds), None);
+        let data_abort = crash(ExcType::DataAbort, thumb, 0x00200006, 0x00100004);
+        assert_eq!(data_abort.interworking_mismatch(&bounds), None);
+        // plugin code only counts for Saltwater crashes
+        let plugin = crash(
+            ExcType::UndefinedInst,
+            thumb,
+            PLUGIN_RANGE.start,
+            0x00100004,
+        );
+        assert_eq!(plugin.interworking_mismatch(&bounds), None);
+    }
+
+    #[test]
+    fn decodes_fault_status() {
+        let abort = |exception_type, dfsr, ifsr| CrashInfo {
+            exception_type,
+            dfsr: Some(dfsr),
+            ifsr: Some(ifsr),
+            ..test_util::crash()
+        };
+        // alignment fault, on a write (WnR, bit 11)
+        assert_eq!(
+            abort(ExcType::DataAbort, 0x801, 0).fault_status(),
+            Some(FAULT_STATUS_ALIGNMENT)
+        );
+        // FS[4] is bit 10 of the DFSR
+        assert_eq!(
+            abort(ExcType::DataAbort, 0x406, 0).fault_status(),
+            Some(0b10110)
+        );
+        assert_eq!(
+            abort(ExcType::PrefetchAbort, 0x401, 0xd).fault_status(),
+            Some(0b1101)
+        );
+        assert_eq!(abort(ExcType::UndefinedInst, 0x1, 0x1).fault_status(), None);
+    }
+}
@@ -0,0 +1,218 @@
+use std::io::Read;
+
+use anyhow::anyhow;
+use csv::Trim;
+
+use crate::crash::analyze::CsvSymbol;
+
+#[derive(Debug, Clone)]
+pub struct OverlaySymbol {
+    pub name: String,
+    pub location: u32,
+    pub size: Option<u32>,
+}
+
+/// Extra symbols provided by a mod author, for code that isn't in Megamix or Saltwater
+/// (code caves, extra memory, custom patches...)
+///
+/// Each symbol covers its size if it has one, otherwise it extends to the next symbol in the
+/// overlay. Inside those ranges, the overlay takes priority over the base symbol tables.
+#[derive(Debug, Clone)]
+pub struct SymbolOverlay {
+    /// Where the overlay came from, usually a file name
+    pub source: String,
+    symbols: Vec<OverlaySymbol>,
+}
+
+impl SymbolOverlay {
+    pub fn new(source: impl Into<String>, mut symbols: Vec<OverlaySymbol>) -> Self {
+        symbols.sort_by_key(|c| c.location);
+        Self {
+            source: source.into(),
+            symbols,
+        }
+    }
+
+    /// Reads an overlay in the same CSV format as the base symbol tables (`name`, `location` and
+    /// optionally `namespace` and `size`)
+    pub fn from_csv(source: impl Into<String>, f: impl Read) -> anyhow::Result<Self> {
+        let mut builder = csv::ReaderBuilder::new();
+        builder.trim(Trim::Fields);
+
+        let symbols = builder
+            .from_reader(f)
+            .deserialize::<CsvSymbol>()
+            .map(|c| {
+                c.map(|c| OverlaySymbol {
+                    name: c.full_name(),
+                    location: c.location,
+                    size: c.size,
+                })
+            })
+            .try_collect()?;
+        Ok(Self::new(source, symbols))
+    }
+
+    /// Reads the symbols from a GNU ld linker map (as made with `-Wl,-Map`). Symbol sizes are
+    /// limited to the end of the input section they're in.
+    pub fn from_linker_map(source: impl Into<String>, map: &str) -> anyhow::Result<Self> {
+        let mut symbols: Vec<OverlaySymbol> = vec![];
+        let mut section_end = None;
+        let mut pending_section = false;
+
+        let start = map.find("Linker script and memory map").unwrap_or_default();
+        for line in map[start..].lines() {
+            let tokens = line.split_whitespace().collect::<Vec<_>>();
+            // sections look like `.text.name 0xADDR 0xSIZE [file.o]`, but the name might be in the
+            // previous line if it's too long
+            let section = match tokens.as_slice() {
+                [name, addr, size, ..] if name.starts_with('.') => Some((addr, size)),
+                [addr, size, ..] if pending_section => Some((addr, size)),
+                _ => None,
+            };
+            pending_section = matches!(tokens.as_slice(), [name] if name.starts_with('.'));
+
+            if let Some((addr, size)) = section
+                && let Some(addr) = parse_hex(addr)
+                && let Some(size) = parse_hex(size)
+            {
+                // a section that would run past the end of memory is garbage, so the symbols in it
+                // don't get a size from it
+                section_end = addr.checked_add(size);
+            } else if let [addr, name] = tokens.as_slice()
+                && let Some(addr) = parse_hex(addr)
+                && !name.contains(['=', '(', '*'])
+            {
+                symbols.push(OverlaySymbol {
+                    name: name.to_string(),
+                    location: addr,
+                    size: section_end.and_then(|c| c.checked_sub(addr)),
+                });
+            }
+        }
+
+        if symbols.is_empty() {
+            Err(anyhow!("Couldn't find any symbols in the linker map"))?
+        }
+        // symbols that share an input section end where the next one starts
+        symbols.sort_by_key(|c| c.location);
+        for i in 0..symbols.len() - 1 {
+            let next = symbols[i + 1].location;
+            let sym = &mut symbols[i];
+            if let Some(size) = sym.size
+                && next > sym.location
+            {
+                sym.size = Some(size.min(next - sym.location));
+            }
+        }
+        Ok(Self::new(source, symbols))
+    }
+
+    /// Whether a file looks like an overlay from its name, as in a symbol CSV or a linker map
+    pub fn is_overlay_file(name: &str) -> bool {
+        name.ends_with(".csv") || name.ends_with(".map")
+    }
+
+    /// Reads an overlay, guessing its format from the file name
+    pub fn from_file(name: &str, contents: &[u8]) -> anyhow::Result<Self> {
+        if name.ends_with(".csv") {
+            Self::from_csv(name, contents)
+        } else if name.ends_with(".map") {
+            Self::from_linker_map(name, &String::from_utf8_lossy(contents))
+        } else {
+            Err(anyhow!(
+                "Unknown symbol overlay format for {name} (should be a .csv or a linker .map)"
+            ))
+        }
+    }
+
+    pub fn symbols(&self) -> &[OverlaySymbol] {
+        &self.symbols
+    }
+
+    /// Finds the symbol in the overlay that covers `pos`, if any
+    pub fn find(&self, pos: u32) -> Option<&OverlaySymbol> {
+        let idx = self.symbols.partition_point(|c| c.location <= pos);
+        let sym = self.symbols.get(idx.checked_sub(1)?)?;
+        let end = match sym.size {
+            Some(size) => sym.location.saturating_add(size),
+            None => self.symbols.get(idx)?.location,
+        };
+        (pos < end).then_some(sym)
+    }
+}
+
+fn parse_hex(s: &str) -> Option<u32> {
+    u32::from_str_radix(s.strip_prefix("0x")?, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_csv() {
+        let csv = "name,location,namespace,size\n\
+                   hook, 00300000, Global, 00000010\n\
+                   cave, 00300100, Mod, \n";
+        let overlay = SymbolOverlay::from_csv("mod.csv", csv.as_bytes()).unwrap();
+        let hook = overlay.find(0x30000c).unwrap();
+        assert_eq!((hook.name.as_str(), hook.size), ("hook", Some(0x10)));
+        assert!(overlay.find(0x300010).is_none());
+        // the last symbol has no size and nothing after it, so it covers nothing
+        assert!(overlay.find(0x300100).is_none());
+        assert_eq!(overlay.symbols()[1].name, "Mod::cave");
+    }
+
+    #[test]
+    fn reads_linker_map() {
+        let map = "\
+Archive member included to satisfy reference by file (symbol)
+
+Linker script and memory map
+
+ .text          0x0000000000300000       0x40 build/hooks.o
+                0x0000000000300000                hook_a
+                0x0000000000300010                hook_b
+ .text.a_function_with_a_long_name
+                0x0000000000300040       0x20 build/long.o
+                0x0000000000300040                long_function
+                0x0000000000300080                PROVIDE (end = .)
+";
+        let overlay = SymbolOverlay::from_linker_map("mod.map", map).unwrap();
+        let symbols = overlay
+            .symbols()
+            .iter()
+            .map(|c| (c.name.as_str(), c.location, c.size))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            symbols,
+            [
+                ("hook_a", 0x300000, Some(0x10)),
+                ("hook_b", 0x300010, Some(0x30)),
+                ("long_function", 0x300040, Some(0x20)),
+            ]
+        );
+        assert!(SymbolOverlay::from_linker_map("empty.map", "").is_err());
+    }
+
+    #[test]
+    fn rejects_sections_past_end_of_memory() {
+        let map = "\
+Linker script and memory map
+
+ .text          0x00000000ffffff00      0x200 build/broken.o
+                0x00000000ffffff00                broken
+";
+        let overlay = SymbolOverlay::from_linker_map("broken.map", map).unwrap();
+        assert_eq!(overlay.symbols()[0].size, None);
+    }
+
+    #[test]
+    fn picks_format_from_file_name() {
+        assert!(SymbolOverlay::is_overlay_file("mod.csv"));
+        assert!(SymbolOverlay::is_overlay_file("mod.map"));
+        assert!(!SymbolOverlay::is_overlay_file("screenshot.png"));
+        assert!(SymbolOverlay::from_file("mod.tickflow", b"").is_err());
+    }
+}
@@ -0,0 +1,1038 @@
+// Data-driven crash solver rules, loaded from a TOML file (by default data/solve/rules.toml)
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use anyhow::anyhow;
+use serde::Deserialize;
+
+use crate::{
+    crash::{
+        analyze::{CsvBounds, Symbols},
+        saltwater::Region,
+        solve::{Diagnosis, Explanation, SceneAsset, Solution, SolveDiagnosis, StackStatus},
+        store::SymbolStore,
+        CrashInfo, ExcType, ModdingEngine,
+    },
+    ctrplugin::PLUGIN_RANGE,
+    faq::QUESTIONS,
+};
+
+/// An address in Megamix or Saltwater, either given for each region or as a symbol.
+///
+/// The symbol takes priority if the crash's symbols have it, so a location can give both. If
+/// there's no address for a region, but there is for another one, it's translated through the
+/// Megamix symbol it's in (the same symbol plus the same offset, in the other region).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Location {
+    pub jp: Option<u32>,
+    pub us: Option<u32>,
+    pub eu: Option<u32>,
+    pub kr: Option<u32>,
+    pub symbol: Option<String>,
+    #[serde(default)]
+    pub offset: u32,
+}
+
+impl Location {
+    pub fn for_region(&self, region: Region) -> Option<u32> {
+        match region {
+            Region::JP => self.jp,
+            Region::US => self.us,
+            Region::EU => self.eu,
+            Region::KR => self.kr,
+            Region::UNK => None,
+        }
+    }
+
+    /// Region and address to translate from, if there's no address for the region that crashed
+    fn translate_from(&self, region: Region) -> Option<(Region, u32)> {
+        if self.for_region(region).is_some() {
+            return None;
+        }
+        [Region::US, Region::EU, Region::JP, Region::KR]
+            .into_iter()
+            .find_map(|c| Some((c, self.for_region(c)?)))
+    }
+
+    fn resolve(&self, region: Region, names: &Resolved) -> Option<u32> {
+        if let Some(symbol) = &self.symbol
+            && let Some(c) = names.names.get(symbol)
+        {
+            return Some(c + self.offset);
+        }
+        if let Some(c) = self.for_region(region) {
+            return Some(c);
+        }
+        let (name, offset) = names.translated.get(&self.translate_from(region)?)?;
+        names.names.get(name).map(|c| c + offset)
+    }
+}
+
+/// Everything about the crash the rules need symbols to work out
+#[derive(Debug, Default)]
+struct Resolved {
+    names: HashMap<String, u32>,
+    /// Address in another region -> symbol name and offset
+    translated: HashMap<(Region, u32), (String, u32)>,
+    /// Deep call stack of the crash
+    frames: Vec<u32>,
+    /// Start of the function PC and each frame are in
+    frame_functions: HashSet<u32>,
+    /// Start of the function each `call_stack_function` location is in
+    functions: HashMap<u32, u32>,
+    stack: Option<StackStatus>,
+    /// Heap whose functions the crash went through
+    heap: Option<HeapMatch>,
+}
+
+#[derive(Debug)]
+struct HeapMatch {
+    name: String,
+    /// Whether PC is in one of the heap's functions, instead of somewhere further up the call stack
+    at_pc: bool,
+    /// Whether PC or LR is in one of the heap's functions
+    at_top: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EngineKind {
+    RHMPatch,
+    Saltwater,
+}
+
+impl EngineKind {
+    fn matches(&self, engine: &ModdingEngine) -> bool {
+        matches!(
+            (self, engine),
+            (Self::RHMPatch, ModdingEngine::RHMPatch)
+                | (Self::Saltwater, ModdingEngine::SpiceRack(..))
+        )
+    }
+}
+
+/// What a rule expects SP to be, compared to the known thread stacks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StackCondition {
+    InThread,
+    Overflow,
+    Underflow,
+    UnknownThread,
+    Corrupted,
+}
+
+impl StackCondition {
+    fn matches(&self, status: &StackStatus) -> bool {
+        matches!(
+            (self, status),
+            (Self::InThread, StackStatus::InThread(_))
+                | (Self::Overflow, StackStatus::Overflow(_))
+                | (Self::Underflow, StackStatus::Underflow(_))
+                | (Self::UnknownThread, StackStatus::UnknownThread)
+                | (Self::Corrupted, StackStatus::Corrupted)
+        )
+    }
+}
+
+/// Stack of a thread of Megamix or Saltwater
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThreadStack {
+    pub name: String,
+    /// Lowest address of the stack
+    pub bottom: Location,
+    pub size: u32,
+    /// Only check this thread for crashes from this engine
+    pub engine: Option<EngineKind>,
+}
+
+/// A heap of Megamix or Saltwater, recognized by the functions that work on it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Heap {
+    pub name: String,
+    /// Names of the functions that allocate from or free into the heap. `*` matches any text
+    pub functions: Vec<String>,
+    /// Only check this heap for crashes from this engine
+    pub engine: Option<EngineKind>,
+}
+
+impl Heap {
+    fn owns(&self, symbol: &str) -> bool {
+        self.functions.iter().any(|c| wildcard_match(c, symbol))
+    }
+}
+
+/// Where a heap function has to be for a rule to match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeapCondition {
+    /// PC is in a heap function
+    Pc,
+    /// PC or LR is in a heap function, so the crash happened in the heap or right after calling it
+    Top,
+    /// A heap function is anywhere in the call stack (or at PC)
+    CallStack,
+}
+
+/// Everything that has to be true about a crash for a rule to match
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Conditions {
+    pub pc: Option<Location>,
+    pub lr: Option<Location>,
+    /// Address that has to be somewhere in the call stack (including the stack dump, if there's one)
+    pub call_stack: Option<Location>,
+    /// Function that has to be somewhere in the call stack (or at PC). Any address inside it counts
+    pub call_stack_function: Option<Location>,
+    /// Range (start inclusive, end exclusive) the fault address has to be in
+    pub far: Option<[u32; 2]>,
+    pub exception: Option<ExcType>,
+    /// Fault status source of a data or prefetch abort, like 0x1 for alignment faults
+    pub fault_status: Option<u32>,
+    /// Whether the CPU has to be in Thumb state
+    pub thumb: Option<bool>,
+    /// Whether the crash has to look like a branch to code of the wrong state (ARM or Thumb)
+    pub interworking_mismatch: Option<bool>,
+    /// Where SP is, compared to the stacks in the `thread` list
+    pub stack: Option<StackCondition>,
+    /// Name of the thread the crash has to happen in
+    pub thread: Option<String>,
+    /// Where a function of one of the heaps in the `heap` list has to be
+    pub heap: Option<HeapCondition>,
+    pub engine: Option<EngineKind>,
+    /// Whether the PC is in Megamix's code or inside the Saltwater plugin
+    pub pc_executable: Option<bool>,
+}
+
+impl Conditions {
+    fn locations(&self) -> impl Iterator<Item = &Location> {
+        [
+            &self.pc,
+            &self.lr,
+            &self.call_stack,
+            &self.call_stack_function,
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+/// What's known about the asset a scene loading rule is about
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneRule {
+    pub asset: Option<SceneAsset>,
+    /// Register (0 to 12) with the slot the asset was being loaded into
+    pub slot_register: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+    pub id: String,
+    /// Built-in diagnosis this rule reports. If not given, it's reported as `SolveDiagnosis::Other`
+    pub kind: Option<String>,
+    /// Only match if no other rule did
+    #[serde(default)]
+    pub fallback: bool,
+    /// IDs of rules that, if they match, make this one not match
+    #[serde(default)]
+    pub unless: Vec<String>,
+    #[serde(default)]
+    pub when: Conditions,
+    /// Asset and slot for `scene_loading_error` and `low_slot_layout` rules
+    pub scene: Option<SceneRule>,
+
+    /// `{pc}`, `{lr}` and `{far}` get replaced with the values from the crash
+    #[serde(flatten)]
+    pub explanation: Explanation,
+}
+
+impl Rule {
+    /// Keys of a rule besides the explanation's. Since the explanation is flattened into the rule,
+    /// serde can't catch unknown keys, so `RuleSet::from_file` checks them against these.
+    const FIELDS: &[&str] = &["id", "kind", "fallback", "unless", "when", "scene"];
+
+    fn matches(&self, crash: &CrashInfo, bounds: &CsvBounds, resolved: &Resolved) -> bool {
+        let region = crash.region();
+        let when = &self.when;
+        let at = |loc: &Option<Location>, pos: u32| match loc {
+            Some(loc) => loc.resolve(region, resolved) == Some(pos),
+            None => true,
+        };
+
+        at(&when.pc, crash.pc)
+            && at(&when.lr, crash.lr)
+            && when.call_stack.as_ref().is_none_or(|loc| {
+                loc.resolve(region, resolved)
+                    .is_some_and(|c| resolved.frames.contains(&c))
+            })
+            && when.call_stack_function.as_ref().is_none_or(|loc| {
+                loc.resolve(region, resolved)
+                    .and_then(|c| resolved.functions.get(&c))
+                    .is_some_and(|c| resolved.frame_functions.contains(c))
+            })
+            && when
+                .far
+                .is_none_or(|[start, end]| crash.far.is_some_and(|c| c >= start && c < end))
+            && when
+                .exception
+                .as_ref()
+                .is_none_or(|c| *c == crash.exception_type)
+            && when
+                .fault_status
+                .is_none_or(|c| crash.fault_status() == Some(c))
+            && when.thumb.is_none_or(|c| crash.is_thumb() == c)
+            && when
+                .interworking_mismatch
+                .is_none_or(|c| crash.interworking_mismatch(bounds).is_some() == c)
+            && when.engine.is_none_or(|c| c.matches(&crash.engine))
+            && when
+                .stack
+                .is_none_or(|c| resolved.stack.as_ref().is_some_and(|s| c.matches(s)))
+            && when.heap.is_none_or(|c| {
+                resolved.heap.as_ref().is_some_and(|h| match c {
+                    HeapCondition::Pc => h.at_pc,
+                    HeapCondition::Top => h.at_top,
+                    HeapCondition::CallStack => true,
+                })
+            })
+            && when.thread.as_ref().is_none_or(|c| {
+                resolved
+                    .stack
+                    .as_ref()
+                    .and_then(|c| c.thread())
+                    .is_some_and(|s| s == c)
+            })
+            && when
+                .pc_executable
+                .is_none_or(|exec| pc_executable(crash, bounds) == exec)
+    }
+
+    fn diagnosis(
+        &self,
+        crash: &CrashInfo,
+        bounds: &CsvBounds,
+        resolved: &Resolved,
+    ) -> anyhow::Result<Diagnosis> {
+        let mut kind = match &self.kind {
+            Some(kind) => {
+                SolveDiagnosis::from_kind(kind, crash, bounds, resolved.stack.as_ref()).ok_or(
+                    anyhow!("Solver rule {} has an unknown kind: {kind}", self.id),
+                )?
+            }
+            None => SolveDiagnosis::Other(self.id.clone()),
+        };
+        if let SolveDiagnosis::SceneLoadingError(scene) = &mut kind
+            && let Some(info) = &self.scene
+        {
+            if info.asset.is_some() {
+                scene.asset = info.asset;
+            }
+            scene.slot = info
+                .slot_register
+                .and_then(|c| crash.r.and_then(|r| r.get(c).copied()));
+        }
+        if let SolveDiagnosis::AllocationFailed(heap) | SolveDiagnosis::HeapCorrupted(heap) =
+            &mut kind
+        {
+            *heap = resolved.heap.as_ref().map(|c| c.name.clone());
+        }
+        Ok(Diagnosis {
+            explanation: self.explanation.filled(crash, &kind),
+            kind,
+        })
+    }
+}
+
+fn pc_executable(crash: &CrashInfo, bounds: &CsvBounds) -> bool {
+    crash.pc < bounds.rodata
+        || matches!(crash.engine, ModdingEngine::SpiceRack(..)) && PLUGIN_RANGE.contains(&crash.pc)
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    #[serde(rename = "rule", default)]
+    pub rules: Vec<Rule>,
+    #[serde(rename = "thread", default)]
+    pub threads: Vec<ThreadStack>,
+    #[serde(rename = "heap", default)]
+    pub heaps: Vec<Heap>,
+}
+
+impl RuleSet {
+    pub const DEFAULT_PATH: &str = "data/solve/rules.toml";
+    pub const PATH_ENV_VAR: &str = "BERTRAM_RULES";
+    /// How far past either end of a stack SP can be and still count as overflowing/underflowing it
+    pub const STACK_GUARD_SIZE: u32 = 0x1000;
+
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let table: toml::Table = toml::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| anyhow!("Invalid solver rules in {}: {e}", path.display()))?;
+        for rule in table
+            .get("rule")
+            .and_then(|c| c.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|c| c.as_table())
+        {
+            if let Some(key) = rule.keys().find(|c| {
+                !Rule::FIELDS.contains(&c.as_str()) && !Explanation::FIELDS.contains(&c.as_str())
+            }) {
+                Err(anyhow!(
+                    "Invalid solver rules in {}: rule {} has an unknown key `{key}`",
+                    path.display(),
+                    rule.get("id")
+                        .and_then(|c| c.as_str())
+                        .unwrap_or("(without an id)")
+                ))?
+            }
+        }
+        let rules: Self = table
+            .try_into()
+            .map_err(|e| anyhow!("Invalid solver rules in {}: {e}", path.display()))?;
+
+        let mut ids = HashSet::new();
+        for rule in &rules.rules {
+            if !ids.insert(&rule.id) {
+                Err(anyhow!("Duplicate solver rule: {}", rule.id))?
+            }
+            if let Some(kind) = &rule.kind
+                && !SolveDiagnosis::KINDS.contains(&kind.as_str())
+            {
+                Err(anyhow!(
+                    "Solver rule {} has an unknown kind: {kind}",
+                    rule.id
+                ))?
+            }
+            if let Some(c) = rule
+                .unless
+                .iter()
+                .find(|c| !rules.rules.iter().any(|r| r.id == **c))
+            {
+                Err(anyhow!(
+                    "Solver rule {} depends on an unknown rule: {c}",
+                    rule.id
+                ))?
+            }
+            if let Some(thread) = &rule.when.thread
+                && !rules.threads.iter().any(|c| c.name == *thread)
+            {
+                Err(anyhow!(
+                    "Solver rule {} checks for an unknown thread: {thread}",
+                    rule.id
+                ))?
+            }
+            if rule
+                .scene
+                .as_ref()
+                .and_then(|c| c.slot_register)
+                .is_some_and(|c| c > 12)
+            {
+                Err(anyhow!(
+                    "Solver rule {} has an invalid slot register",
+                    rule.id
+                ))?
+            }
+            if let Some(faq) = rule
+                .explanation
+                .faq
+                .iter()
+                .find(|name| !QUESTIONS.iter().any(|q| q.name == *name))
+            {
+                Err(anyhow!(
+                    "Solver rule {} links to an unknown FAQ: {faq}",
+                    rule.id
+                ))?
+            }
+        }
+        let mut names = HashSet::new();
+        for thread in &rules.threads {
+            if !names.insert(&thread.name) {
+                Err(anyhow!("Duplicate thread: {}", thread.name))?
+            }
+        }
+        let mut names = HashSet::new();
+        for heap in &rules.heaps {
+            if !names.insert(&heap.name) {
+                Err(anyhow!("Duplicate heap: {}", heap.name))?
+            }
+        }
+        Ok(rules)
+    }
+
+    /// Loads the rules from `BERTRAM_RULES` if it's set, otherwise from data/solve/rules.toml
+    pub fn from_env() -> anyhow::Result<Self> {
+        Self::from_file(std::env::var(Self::PATH_ENV_VAR).unwrap_or(Self::DEFAULT_PATH.to_string()))
+    }
+
+    /// Finds every rule that matches the crash, in the order they appear in the file. If there's
+    /// no data for the crash's region, the only result is `SolveDiagnosis::NoRegionData`.
+    pub fn find_matches(&self, crash: &CrashInfo, store: &SymbolStore) -> anyhow::Result<Solution> {
+        let region = crash.region();
+        let Ok(bounds) = store.region_bounds(region) else {
+            return Ok(Solution {
+                stack: None,
+                diagnoses: vec![Diagnosis::no_region_data(region)],
+            });
+        };
+        let mut resolved = match store.symbols(&crash.engine) {
+            Ok(mut symbols) => {
+                let mut resolved = self.resolve_names(region, &mut symbols, store)?;
+                resolved.frames = crash.deep_call_stack(&bounds);
+                self.resolve_functions(crash, &mut symbols, &mut resolved)?;
+                resolved
+            }
+            // without symbols, only the addresses given for this region can be checked
+            Err(_)
+                if self
+                    .rules
+                    .iter()
+                    .flat_map(|c| c.when.locations())
+                    .any(|c| c.for_region(region).is_some()) =>
+            {
+                Resolved {
+                    frames: crash.deep_call_stack(&bounds),
+                    ..Default::default()
+                }
+            }
+            Err(_) => {
+                return Ok(Solution {
+                    stack: None,
+                    diagnoses: vec![Diagnosis::no_region_data(region)],
+                });
+            }
+        };
+
+        resolved.stack = self.stack_status(crash, &bounds, &resolved);
+
+        let mut matched = self
+            .rules
+            .iter()
+            .filter(|c| !c.fallback && c.matches(crash, &bounds, &resolved))
+            .collect::<Vec<_>>();
+        if matched.is_empty() {
+            matched = self
+                .rules
+                .iter()
+                .filter(|c| c.fallback && c.matches(crash, &bounds, &resolved))
+                .collect();
+        }
+        let ids = matched
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect::<HashSet<_>>();
+        let diagnoses = matched
+            .iter()
+            .filter(|c| !c.unless.iter().any(|id| ids.contains(id.as_str())))
+            .map(|c| c.diagnosis(crash, &bounds, &resolved))
+            .try_collect()?;
+        Ok(Solution {
+            stack: resolved.stack,
+            diagnoses,
+        })
+    }
+
+    /// Works out which thread's stack SP is in. Without an SP there's nothing to check, and
+    /// without any threads for the crash's engine all that can be told is whether SP is corrupted.
+    fn stack_status(
+        &self,
+        crash: &CrashInfo,
+        bounds: &CsvBounds,
+        resolved: &Resolved,
+    ) -> Option<StackStatus> {
+        let sp = crash.sp?;
+        // stacks are word-aligned, and can't be in the code or below it
+        if sp % 4 != 0 || sp < bounds.data || sp >= 0x40000000 {
+            return Some(StackStatus::Corrupted);
+        }
+
+        let region = crash.region();
+        let stacks = self
+            .threads
+            .iter()
+            .filter(|c| c.engine.is_none_or(|c| c.matches(&crash.engine)))
+            .filter_map(|c| Some((c.name.clone(), c.bottom.resolve(region, resolved)?, c.size)))
+            .collect::<Vec<_>>();
+        let guard = Self::STACK_GUARD_SIZE;
+
+        if let Some((name, bottom, _)) = stacks
+            .iter()
+            .find(|(_, bottom, size)| (*bottom..bottom + size).contains(&sp))
+        {
+            // a push that faults right below the stack doesn't get to update SP
+            if crash.exception_type == ExcType::DataAbort
+                && crash
+                    .far
+                    .is_some_and(|c| (bottom.saturating_sub(guard)..*bottom).contains(&c))
+            {
+                return Some(StackStatus::Overflow(name.clone()));
+            }
+            return Some(StackStatus::InThread(name.clone()));
+        }
+        if let Some((name, ..)) = stacks
+            .iter()
+            .find(|(_, bottom, _)| (bottom.saturating_sub(guard)..*bottom).contains(&sp))
+        {
+            return Some(StackStatus::Overflow(name.clone()));
+        }
+        if let Some((name, ..)) = stacks
+            .iter()
+            .find(|(_, bottom, size)| (bottom + size..bottom + size + guard).contains(&sp))
+        {
+            return Some(StackStatus::Underflow(name.clone()));
+        }
+        (!stacks.is_empty()).then_some(StackStatus::UnknownThread)
+    }
+
+    /// Most frames of the deep call stack that get looked up for `call_stack_function`
+    const MAX_SYMBOLIZED_FRAMES: usize = 64;
+
+    /// Finds which function each `call_stack_function` location and each frame are in, and which
+    /// heap's functions the crash went through
+    fn resolve_functions(
+        &self,
+        crash: &CrashInfo,
+        symbols: &mut Symbols,
+        resolved: &mut Resolved,
+    ) -> anyhow::Result<()> {
+        let region = crash.region();
+        let wanted = self
+            .rules
+            .iter()
+            .filter_map(|c| c.when.call_stack_function.as_ref())
+            .filter_map(|c| c.resolve(region, resolved))
+            .collect::<Vec<_>>();
+        let heaps = self
+            .heaps
+            .iter()
+            .filter(|c| c.engine.is_none_or(|c| c.matches(&crash.engine)))
+            .collect::<Vec<_>>();
+        if wanted.is_empty() && heaps.is_empty() {
+            return Ok(());
+        }
+
+        for pos in wanted {
+            if let Some(func) = symbols.find_symbol(pos)? {
+                resolved.functions.insert(pos, func.func_pos);
+            }
+        }
+        let find_heap = |symbol: &str| heaps.iter().find(|c| c.owns(symbol));
+        if let Some(func) = symbols.find_symbol(crash.pc)? {
+            if let Some(heap) = find_heap(&func.symbol) {
+                resolved.heap = Some(HeapMatch {
+                    name: heap.name.clone(),
+                    at_pc: true,
+                    at_top: true,
+                });
+            }
+            resolved.frame_functions.insert(func.func_pos);
+        }
+        if resolved.heap.is_none()
+            && let Some(func) = symbols.find_symbol(crash.lr)?
+            && let Some(heap) = find_heap(&func.symbol)
+        {
+            resolved.heap = Some(HeapMatch {
+                name: heap.name.clone(),
+                at_pc: false,
+                at_top: true,
+            });
+        }
+        for pos in resolved.frames.iter().take(Self::MAX_SYMBOLIZED_FRAMES) {
+            let Some(func) = symbols.find_symbol(*pos)? else {
+                continue;
+            };
+            if resolved.heap.is_none()
+                && let Some(heap) = find_heap(&func.symbol)
+            {
+                resolved.heap = Some(HeapMatch {
+                    name: heap.name.clone(),
+                    at_pc: false,
+                    at_top: false,
+                });
+            }
+            resolved.frame_functions.insert(func.func_pos);
+        }
+        Ok(())
+    }
+
+    fn locations(&self) -> impl Iterator<Item = &Location> {
+        self.rules
+            .iter()
+            .flat_map(|c| c.when.locations())
+            .chain(self.threads.iter().map(|c| &c.bottom))
+    }
+
+    /// Kinds of the rules about errors while loading a scene
+    const SCENE_LOADING_KINDS: &[&str] = &["scene_loading_error", "low_slot_layout"];
+
+    /// Start of every function the scene loading rules check PC, LR or the call stack for, in the
+    /// crash's symbols. A crash in any of them happened while a scene was loading.
+    pub fn scene_loading_functions(
+        &self,
+        region: Region,
+        symbols: &mut Symbols,
+        store: &SymbolStore,
+    ) -> anyhow::Result<HashSet<u32>> {
+        let locations = self
+            .rules
+            .iter()
+            .filter(|c| {
+                c.kind
+                    .as_deref()
+                    .is_some_and(|c| Self::SCENE_LOADING_KINDS.contains(&c))
+            })
+            .flat_map(|c| c.when.locations())
+            .collect::<Vec<_>>();
+        let resolved = Self::resolve_locations(&locations, region, symbols, store)?;
+        let mut out = HashSet::new();
+        for pos in locations
+            .iter()
+            .filter_map(|c| c.resolve(region, &resolved))
+        {
+            if let Some(func) = symbols.find_symbol(pos)? {
+                out.insert(func.func_pos);
+            }
+        }
+        Ok(out)
+    }
+
+    fn resolve_names(
+        &self,
+        region: Region,
+        symbols: &mut Symbols,
+        store: &SymbolStore,
+    ) -> anyhow::Result<Resolved> {
+        Self::resolve_locations(
+            &self.locations().collect::<Vec<_>>(),
+            region,
+            symbols,
+            store,
+        )
+    }
+
+    /// Finds the symbols the locations are given by, and translates the ones only given for
+    /// other regions
+    fn resolve_locations(
+        locations: &[&Location],
+        region: Region,
+        symbols: &mut Symbols,
+        store: &SymbolStore,
+    ) -> anyhow::Result<Resolved> {
+        let mut out = Resolved::default();
+
+        let mut to_translate = locations
+            .iter()
+            .filter_map(|c| c.translate_from(region))
+            .collect::<Vec<_>>();
+        to_translate.sort_by_key(|c| (c.0 as u8, c.1));
+        to_translate.dedup();
+        let mut source_symbols = None;
+        for (source, pos) in to_translate {
+            if source_symbols
+                .as_ref()
+                .is_none_or(|c: &(Region, _)| c.0 != source)
+            {
+                source_symbols = store.megamix_symbols(source).ok().map(|c| (source, c));
+            }
+            if let Some((_, symbols)) = &mut source_symbols
+                && let Some(func) = symbols.find_symbol(pos)?
+            {
+                out.translated
+                    .insert((source, pos), (func.symbol, pos - func.func_pos));
+            }
+        }
+
+        let wanted = locations
+            .iter()
+            .filter_map(|c| c.symbol.as_deref())
+            .chain(out.translated.values().map(|c| c.0.as_str()))
+            .collect::<HashSet<_>>();
+        if !wanted.is_empty() {
+            out.names = symbols.find_names(&wanted)?;
+        }
+        Ok(out)
+    }
+}
+
+/// Matches `name` against a pattern where `*` stands for any text
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = name.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crash::saltwater::SWDType,
+        test_util::{self, TempDir},
+    };
+
+    fn load(name: &str, rules: &str) -> anyhow::Result<RuleSet> {
+        let dir = TempDir::with_files(name, &[("rules.toml", rules)]);
+        RuleSet::from_file(dir.path().join("rules.toml"))
+    }
+
+    /// Store with bounds for US, EU and KR, but symbols only for US and EU
+    fn store(name: &str) -> (TempDir, SymbolStore) {
+        let dir = TempDir::with_files(
+            name,
+            &[
+                (
+                    SymbolStore::MANIFEST_NAME,
+                    r#"
+                    bounds.file = "bounds.csv"
+                    region = [
+                        { region = "us", file = "rhm.us.csv" },
+                        { region = "eu", file = "rhm.eu.csv" },
+                        { region = "kr", file = "rhm.kr.csv" },
+                    ]
+                    "#,
+                ),
+                (
+                    "bounds.csv",
+                    "Version,Code offset,Rodata offset,Data offset,BSS start,BSS size
+                    US,00100000,0039A000,00521000,0054E074,0008D27C
+                    EU,00100000,0039A000,00521000,0054E16C,0008D184
+                    KR,00100000,0039A000,00521000,0054E16C,0008D184",
+                ),
+                (
+                    "rhm.us.csv",
+                    "name,location,namespace\nFUN_a,00200000,\nFUN_b,00200100,\n",
+                ),
+                (
+                    "rhm.eu.csv",
+                    "name,location,namespace\nFUN_a,00210000,\nFUN_b,00210100,\n",
+                ),
+            ],
+        );
+        let store = SymbolStore::new(dir.path()).unwrap();
+        (dir, store)
+    }
+
+    #[test]
+    fn loads_shipped_rules() {
+        let rules =
+            RuleSet::from_file(Path::new(env!("CARGO_MANIFEST_DIR")).join(RuleSet::DEFAULT_PATH))
+                .unwrap();
+        assert!(!rules.rules.is_empty());
+    }
+
+    #[test]
+    fn loads_rule() {
+        let rules = load(
+            "rule",
+            r#"
+            [[rule]]
+            id = "low_slot_layout"
+            kind = "low_slot_layout"
+            title = "Tried to load a layout into slot {slot}"
+            scene.slot_register = 1
+            when.pc = { us = 0x0020b494 }
+            "#,
+        )
+        .unwrap();
+        let rule = &rules.rules[0];
+        assert_eq!(
+            rule.explanation.title,
+            "Tried to load a layout into slot {slot}"
+        );
+        assert_eq!(rule.scene.as_ref().unwrap().slot_register, Some(1));
+        assert_eq!(rule.when.pc.as_ref().unwrap().us, Some(0x0020b494));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let unknown = |name, rules| load(name, rules).unwrap_err().to_string();
+        assert!(unknown(
+            "rule-key",
+            "[[rule]]\nid = \"a\"\ntitle = \"A\"\ntitel = \"A\""
+        )
+        .contains("rule a has an unknown key `titel`"));
+        assert!(unknown(
+            "when-key",
+            "[[rule]]\nid = \"a\"\ntitle = \"A\"\nwhen.stak = \"overflow\""
+        )
+        .contains("stak"));
+        assert!(unknown("top-key", "[[rules]]\nid = \"a\"\ntitle = \"A\"").contains("rules"));
+    }
+
+    #[test]
+    fn rejects_bad_references() {
+        let error = |name, rules| load(name, rules).unwrap_err().to_string();
+        assert_eq!(
+            error(
+                "duplicate",
+                "[[rule]]\nid = \"a\"\ntitle = \"A\"\n[[rule]]\nid = \"a\"\ntitle = \"B\""
+            ),
+            "Duplicate solver rule: a"
+        );
+        assert_eq!(
+            error("kind", "[[rule]]\nid = \"a\"\nkind = \"b\"\ntitle = \"A\""),
+            "Solver rule a has an unknown kind: b"
+        );
+        assert_eq!(
+            error(
+                "unless",
+                "[[rule]]\nid = \"a\"\nunless = [\"b\"]\ntitle = \"A\""
+            ),
+            "Solver rule a depends on an unknown rule: b"
+        );
+        assert_eq!(
+            error(
+                "thread",
+                "[[rule]]\nid = \"a\"\nwhen.thread = \"b\"\ntitle = \"A\""
+            ),
+            "Solver rule a checks for an unknown thread: b"
+        );
+    }
+
+    #[test]
+    fn wildcards_match_any_text() {
+        assert!(wildcard_match(
+            "nn::fnd::ExpHeapBase::*",
+            "nn::fnd::ExpHeapBase::Allocate"
+        ));
+        assert!(wildcard_match(
+            "nn::fnd::detail::*Heap*",
+            "nn::fnd::detail::ExpHeapImpl::Free"
+        ));
+        assert!(wildcard_match("*alloc", "malloc"));
+        assert!(wildcard_match("exact", "exact"));
+        assert!(!wildcard_match("exact", "exactly"));
+        assert!(!wildcard_match("nn::fnd::*Heap*", "nn::os::Thread"));
+        assert!(!wildcard_match("a*b*a", "ab"));
+    }
+
+    #[test]
+    fn heaps_own_their_functions() {
+        let heap = Heap {
+            name: "Megamix".to_string(),
+            functions: vec!["nn::fnd::ExpHeapBase::*".to_string()],
+            engine: None,
+        };
+        assert!(heap.owns("nn::fnd::ExpHeapBase::Allocate"));
+        assert!(!heap.owns("nn::fnd::FrameHeapBase::Allocate"));
+    }
+
+    #[test]
+    fn resolves_symbols_before_addresses() {
+        let location = Location {
+            us: Some(0x00200110),
+            symbol: Some("LayoutLoader::load".to_string()),
+            offset: 0x10,
+            ..Default::default()
+        };
+        let mut resolved = Resolved::default();
+        // without the symbol, the US address is used, and translated to EU through FUN_b
+        resolved
+            .translated
+            .insert((Region::US, 0x00200110), ("FUN_b".to_string(), 0x10));
+        resolved.names.insert("FUN_b".to_string(), 0x00210100);
+        assert_eq!(location.resolve(Region::US, &resolved), Some(0x00200110));
+        assert_eq!(location.resolve(Region::EU, &resolved), Some(0x00210110));
+
+        resolved
+            .names
+            .insert("LayoutLoader::load".to_string(), 0x00300000);
+        assert_eq!(location.resolve(Region::US, &resolved), Some(0x00300010));
+        assert_eq!(location.resolve(Region::EU, &resolved), Some(0x00300010));
+    }
+
+    #[test]
+    fn matches_crashes_from_other_regions() {
+        let rules = load(
+            "regions",
+            "[[rule]]\nid = \"crash_in_b\"\ntitle = \"B\"\nwhen.pc = { us = 0x00200110 }",
+        )
+        .unwrap();
+        let (_dir, store) = store("regions-store");
+        let solve = |region, pc| {
+            let crash = CrashInfo {
+                engine: ModdingEngine::SpiceRack(SWDType::Extended, "0.2".parse().unwrap(), region),
+                pc,
+                ..test_util::crash()
+            };
+            rules
+                .find_matches(&crash, &store)
+                .unwrap()
+                .diagnoses
+                .into_iter()
+                .map(|c| c.kind)
+                .collect::<Vec<_>>()
+        };
+
+        // the address is only given for US, so it's found in EU through the symbol it's in
+        let eu = solve(Region::EU, 0x00210110);
+        assert!(matches!(&eu[..], [SolveDiagnosis::Other(c)] if c == "crash_in_b"));
+        assert!(solve(Region::EU, 0x00200110).is_empty());
+        // KR has bounds but no symbols, and UNK has neither
+        for region in [Region::KR, Region::UNK] {
+            let kinds = solve(region, 0x00210110);
+            assert!(matches!(&kinds[..], [SolveDiagnosis::NoRegionData(c)] if *c == region));
+        }
+    }
+
+    #[test]
+    fn finds_where_sp_is() {
+        let rules = load(
+            "threads",
+            "[[thread]]\nname = \"main\"\nbottom.us = 0x0fff0000\nsize = 0x10000",
+        )
+        .unwrap();
+        let bounds = test_util::bounds();
+        let status = |sp: u32, far: Option<u32>| {
+            let crash = CrashInfo {
+                sp: Some(sp),
+                far,
+                ..test_util::crash()
+            };
+            rules.stack_status(&crash, &bounds, &Resolved::default())
+        };
+        let main = || "main".to_string();
+
+        assert_eq!(
+            status(0x0fff8000, None),
+            Some(StackStatus::InThread(main()))
+        );
+        assert_eq!(
+            status(0x0ffefff0, None),
+            Some(StackStatus::Overflow(main()))
+        );
+        // SP is still in the stack, but the push right below it faulted
+        assert_eq!(
+            status(0x0fff0004, Some(0x0ffefffc)),
+            Some(StackStatus::Overflow(main()))
+        );
+        assert_eq!(
+            status(0x10000010, None),
+            Some(StackStatus::Underflow(main()))
+        );
+        assert_eq!(status(0x0f000000, None), Some(StackStatus::UnknownThread));
+        assert_eq!(status(0x0fff8002, None), Some(StackStatus::Corrupted));
+        assert_eq!(status(0x00200000, None), Some(StackStatus::Corrupted));
+        assert_eq!(status(0x40000000, None), Some(StackStatus::Corrupted));
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
     fmt::Display,
     io::{Read, Seek, SeekFrom},
+    str::FromStr,
 };
 
 use anyhow::anyhow;
@@ -27,7 +28,7 @@ impl TryFrom<u8> for SWDType {
 }
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Region {
     JP,
     US,
@@ -60,6 +61,16 @@ impl Display for Region {
     }
 }
 
+impl FromStr for Region {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [Self::JP, Self::US, Self::EU, Self::KR]
+            .into_iter()
+            .find(|c| c.matches(s))
+            .ok_or(anyhow!("invalid region {s} (should be JP/US/EU/KR)"))
+    }
+}
+
 impl From<u8> for Region {
     fn from(value: u8) -> Self {
         match value {
@@ -72,7 +83,7 @@ impl From<u8> for Region {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SWDVersion {
     Debug { commit_hash: String },
     Release { major: u8, minor: u8, patch: u8 },
@@ -101,6 +112,55 @@ impl Display for SWDVersion {
     }
 }
 
+/// Releases are ordered by version number. Debug builds can't be ordered from their
+/// commit hash alone, so they're only comparable to themselves.
+impl PartialOrd for SWDVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (
+                Self::Release {
+                    major,
+                    minor,
+                    patch,
+                },
+                Self::Release {
+                    major: o_major,
+                    minor: o_minor,
+                    patch: o_patch,
+                },
+            ) => Some((major, minor, patch).cmp(&(o_major, o_minor, o_patch))),
+            (a, b) if a == b => Some(std::cmp::Ordering::Equal),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for SWDVersion {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.contains('.') {
+            let mut parts = s.split('.').map(str::parse::<u8>);
+            let (Some(Ok(major)), Some(Ok(minor)), patch, None) =
+                (parts.next(), parts.next(), parts.next(), parts.next())
+            else {
+                Err(anyhow!("invalid Saltwater version {s}"))?
+            };
+            Ok(Self::Release {
+                major,
+                minor,
+                patch: patch.transpose()?.unwrap_or(0),
+            })
+        } else {
+            let commit_hash = s.trim_start_matches('_').to_lowercase();
+            if commit_hash.is_empty() || !commit_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                Err(anyhow!("invalid Saltwater version or commit hash {s}"))?
+            }
+            Ok(Self::Debug { commit_hash })
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CrashSWD {
     pub crash_type: SWDType,
@@ -148,7 +208,7 @@ impl CrashSWD {
             out
         } else {
             SWDVersion::Debug {
-                commit_hash: format!("{:x}", u32::read_from(f, LE)?),
+                commit_hash: format!("{:07x}", u32::read_from(f, LE)?),
             }
         };
 
@@ -215,6 +275,7 @@ impl CrashSWD {
     pub fn as_generic(&self) -> CrashInfo {
         CrashInfo {
             engine: ModdingEngine::SpiceRack(self.crash_type, self.version.clone(), self.region),
+            exception_type: self.exception_type.clone(),
             r: self.registers.map(|c| c[..13].try_into().unwrap()),
             sp: self.registers.map(|c| c[13]),
             lr: self.lr,
@@ -251,3 +312,53 @@ impl CrashSWD {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(s: &str) -> SWDVersion {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_releases_and_commits() {
+        assert_eq!(
+            version("0.2"),
+            SWDVersion::Release {
+                major: 0,
+                minor: 2,
+                patch: 0
+            }
+        );
+        assert_eq!(
+            version("1.2.3"),
+            SWDVersion::Release {
+                major: 1,
+                minor: 2,
+                patch: 3
+            }
+        );
+        assert_eq!(
+            version("_ABC1234"),
+            SWDVersion::Debug {
+                commit_hash: "abc1234".to_string()
+            }
+        );
+        assert!("0.2.3.4".parse::<SWDVersion>().is_err());
+        assert!("xyz".parse::<SWDVersion>().is_err());
+    }
+
+    #[test]
+    fn orders_releases_only() {
+        assert!(version("0.2") < version("0.10"));
+        assert!(version("0.2.1") > version("0.2"));
+        assert!(version("1.0") > version("0.9.9"));
+        assert_eq!(
+            version("abc1234").partial_cmp(&version("abc1234")),
+            Some(std::cmp::Ordering::Equal)
+        );
+        assert_eq!(version("abc1234").partial_cmp(&version("0.2")), None);
+        assert_eq!(version("abc1234").partial_cmp(&version("def5678")), None);
+    }
+}
@@ -1,102 +1,735 @@
 // Bertram crash solver
 // The way this works is: 1. get crash 2. detect specific addresses in the PC/LR/call stack 3. profit
+// The addresses and the text for each diagnosis live in the solver rules (see rules.rs)
 
-use super::{saltwater::Region, CrashInfo, ModdingEngine};
+use std::{fmt::Display, str::FromStr};
 
 use anyhow::anyhow;
+use serde::Deserialize;
+
+use super::{analyze::CsvBounds, saltwater::Region, CrashInfo};
+use crate::{
+    faq::{FaqQuestion, QUESTIONS},
+    tickflow::{
+        ArgKind, BinKind, Operation, TickflowBin, OP_ASYNC_CALL, OP_CALL, OP_GOTO, OP_RETURN,
+        OP_SUB,
+    },
+};
 
 #[derive(Clone, Debug)]
 pub enum SolveDiagnosis {
-    InvalidTickflowAddress(Option<u32>),
+    InvalidTickflowAddress {
+        far: Option<u32>,
+        /// Operations that most likely jumped there, if the Tickflow binary was given
+        culprits: Vec<TickflowCulprit>,
+    },
     NoEffectMemory,
     SceneLoadingError(SceneLoadDiagnosis),
     NonExecRegion(u32),
+    /// Data access that needed to be aligned, at this address
+    MisalignedAccess(Option<u32>),
+    /// Branch that didn't switch between ARM and Thumb state as it should have, to this address
+    InterworkingMismatch {
+        target: u32,
+        /// Whether the CPU was in Thumb state (and the code was ARM)
+        thumb: bool,
+    },
     NullRead,
+    /// An allocation failed, in this heap if known
+    AllocationFailed(Option<String>),
+    /// A heap function crashed on a bad pointer, in this heap if known
+    HeapCorrupted(Option<String>),
+    /// SP went past the bottom of this thread's stack
+    StackOverflow(String),
+    /// SP went past the top of this thread's stack
+    StackUnderflow(String),
+    BadStackPointer(Option<u32>),
+    /// Diagnosis from a solver rule that isn't built into Bertram, by rule ID
+    Other(String),
+    /// There's no symbols or bounds for the crash's region, so nothing could be checked
+    NoRegionData(Region),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SceneAsset {
+    Cellanim,
+    Layout,
+    Effect,
+}
+
+impl Display for SceneAsset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Cellanim => "cellanim",
+                Self::Layout => "layout",
+                Self::Effect => "effect",
+            }
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SceneLoadDiagnosis {
+    /// Kind of asset that failed to load, if known
+    pub asset: Option<SceneAsset>,
+    /// Slot the asset was being loaded into, if known
+    pub slot: Option<u32>,
+}
+
+impl SceneLoadDiagnosis {
+    /// Layouts can't be loaded into slots 0 to 3
+    pub fn is_low_slot_layout(&self) -> bool {
+        self.asset == Some(SceneAsset::Layout) && self.slot.is_none_or(|c| c <= 3)
+    }
 }
 
+/// Tickflow operation that most likely jumped to an invalid address
 #[derive(Clone, Debug)]
-pub enum SceneLoadDiagnosis {
-    Generic,
-    LowSlotLayout,
+pub struct TickflowCulprit {
+    pub op: Operation,
+    pub reason: CulpritReason,
 }
 
-impl SolveDiagnosis {
-    pub const fn invalid_tickflow_address_pc(region: Region) -> u32 {
-        match region {
-            Region::US => 0x0011e764,
-            Region::UNK => unreachable!(),
-            _ => todo!(),
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CulpritReason {
+    /// The operation's argument is the invalid address
+    Target,
+    /// The interpreter was at the operation, going by this register
+    Register(usize),
+    /// The operation's argument isn't the kind of value it should be
+    Suspicious,
+}
+
+impl TickflowCulprit {
+    /// Most operations listed when nothing points straight at the culprit
+    const MAX_SUSPICIOUS: usize = 5;
+
+    /// Looks for the operations that jumped to the crash's invalid address: first the ones with
+    /// that address as their argument, then the ones the registers point at, and as a last resort
+    /// jumps with the wrong kind of argument.
+    ///
+    /// A Tickompiler .bin can be loaded anywhere, and the crash doesn't say where, so registers
+    /// can't be matched to its operations. Its sub numbers and other plain arguments aren't
+    /// relocated when it's loaded though, so a `call` to one still shows up as the target, and its
+    /// annotations tell which arguments should have been locations.
+    pub fn find(crash: &CrashInfo, bin: &TickflowBin) -> Vec<Self> {
+        let with_reason = |op: &Operation, reason| Self {
+            op: op.clone(),
+            reason,
+        };
+
+        if let Some(far) = crash.far {
+            let out = bin
+                .ops
+                .iter()
+                .filter(|c| c.jumps_to(far))
+                .map(|c| with_reason(c, CulpritReason::Target))
+                .collect::<Vec<_>>();
+            if !out.is_empty() {
+                return out;
+            }
+        }
+
+        let mut out: Vec<Self> = vec![];
+        for (i, reg) in crash.r.iter().flatten().enumerate() {
+            if let Some(op) = bin.op_near(*reg)
+                && op.is_jump()
+                && !out.iter().any(|c| c.op.offset == op.offset)
+            {
+                out.push(with_reason(op, CulpritReason::Register(i)));
+            }
+        }
+        // without annotations, there's no telling locations apart in a Tickompiler .bin
+        if !out.is_empty() || (bin.base().is_none() && bin.annotations.is_empty()) {
+            return out;
+        }
+
+        // locations are addresses into the file (or marked by Tickompiler), sub numbers aren't
+        let is_location = |op: &Operation, arg: u32| match bin.kind {
+            BinKind::C00(_) => bin.contains(arg),
+            BinKind::Single(_) => {
+                let offset = op.offset + 4;
+                bin.annotations
+                    .iter()
+                    .any(|c| c.offset == offset && c.kind == ArgKind::Location)
+            }
+        };
+        bin.ops
+            .iter()
+            .filter(|c| match (c.opcode, c.args.first()) {
+                (OP_CALL | OP_ASYNC_CALL, Some(arg)) => !is_location(c, *arg),
+                (OP_SUB, arg) => bin.contains(c.arg0) || arg.is_some_and(|d| is_location(c, *d)),
+                _ => false,
+            })
+            .take(Self::MAX_SUSPICIOUS)
+            .map(|c| with_reason(c, CulpritReason::Suspicious))
+            .collect()
+    }
+
+    /// What most likely went wrong with the operation
+    pub fn cause(&self) -> &'static str {
+        match self.op.opcode {
+            OP_CALL | OP_ASYNC_CALL => {
+                "`call`ing a sub by number (`call` needs a location, `sub` takes sub numbers)"
+            }
+            OP_SUB => "`sub`ing a sub by label (`sub` needs a sub number, `call` takes locations)",
+            OP_RETURN => "`return`ing with nothing to return to, like on an async thread",
+            OP_GOTO => "`goto`ing a label that doesn't exist, or a location instead of a label",
+            _ => "Jumping somewhere that isn't Tickflow",
+        }
+    }
+}
+
+impl Display for TickflowCulprit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` at offset 0x{:x}", self.op, self.op.offset)?;
+        match self.reason {
+            CulpritReason::Target => Ok(()),
+            CulpritReason::Register(c) => write!(f, " (r{c} points to it)"),
+            CulpritReason::Suspicious => write!(f, " (suspicious argument)"),
         }
     }
+}
 
-    pub const fn no_effect_memory_pc(region: Region) -> u32 {
-        match region {
-            Region::US => 0x001392c4,
-            Region::UNK => unreachable!(),
-            _ => todo!(),
+/// Where SP was at the time of the crash, compared to the stacks of the known threads
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StackStatus {
+    /// SP is inside this thread's stack
+    InThread(String),
+    /// SP (or the fault address) is right below this thread's stack
+    Overflow(String),
+    /// SP is right above this thread's stack
+    Underflow(String),
+    /// SP could be valid, but it's not in or near any known stack
+    UnknownThread,
+    /// SP can't be a stack pointer (misaligned, or not in writable memory)
+    Corrupted,
+}
+
+impl StackStatus {
+    /// Thread the crash happened in, if known
+    pub fn thread(&self) -> Option<&str> {
+        match self {
+            Self::InThread(c) | Self::Overflow(c) | Self::Underflow(c) => Some(c),
+            Self::UnknownThread | Self::Corrupted => None,
         }
     }
+}
 
-    pub const fn scene_loading_lr(region: Region) -> u32 {
-        match region {
-            Region::US => 0x002471dc,
-            Region::UNK => unreachable!(),
-            _ => todo!(),
+impl Display for StackStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InThread(c) => write!(f, "{c} thread"),
+            Self::Overflow(c) => write!(f, "{c} thread (stack overflow)"),
+            Self::Underflow(c) => write!(f, "{c} thread (stack underflow)"),
+            Self::UnknownThread => write!(f, "unknown thread"),
+            Self::Corrupted => write!(f, "unknown thread (corrupted stack pointer)"),
         }
     }
+}
+
+impl SolveDiagnosis {
+    /// Every `kind` a solver rule can use
+    pub const KINDS: &[&str] = &[
+        "invalid_tickflow_address",
+        "no_effect_memory",
+        "scene_loading_error",
+        "low_slot_layout",
+        "non_exec_region",
+        "misaligned_access",
+        "interworking_mismatch",
+        "null_read",
+        "allocation_failed",
+        "heap_corrupted",
+        "stack_overflow",
+        "stack_underflow",
+        "bad_stack_pointer",
+    ];
 
-    pub const fn forbidden_layout_pc(region: Region) -> u32 {
-        match region {
-            Region::US => 0x0020b494,
-            Region::UNK => unreachable!(),
-            _ => todo!(),
+    /// Built-in diagnosis for the `kind` of a solver rule
+    pub fn from_kind(
+        kind: &str,
+        crash: &CrashInfo,
+        bounds: &CsvBounds,
+        stack: Option<&StackStatus>,
+    ) -> Option<Self> {
+        let thread = || {
+            stack
+                .and_then(|c| c.thread())
+                .unwrap_or("unknown")
+                .to_string()
+        };
+        Some(match kind {
+            "invalid_tickflow_address" => Self::InvalidTickflowAddress {
+                far: crash.far,
+                culprits: vec![],
+            },
+            "no_effect_memory" => Self::NoEffectMemory,
+            "scene_loading_error" => Self::SceneLoadingError(SceneLoadDiagnosis::default()),
+            "low_slot_layout" => Self::SceneLoadingError(SceneLoadDiagnosis {
+                asset: Some(SceneAsset::Layout),
+                slot: None,
+            }),
+            "non_exec_region" => Self::NonExecRegion(crash.pc),
+            "misaligned_access" => Self::MisalignedAccess(crash.far),
+            "interworking_mismatch" => Self::InterworkingMismatch {
+                target: crash.interworking_mismatch(bounds).unwrap_or(crash.pc),
+                thumb: crash.is_thumb(),
+            },
+            "null_read" => Self::NullRead,
+            "allocation_failed" => Self::AllocationFailed(None),
+            "heap_corrupted" => Self::HeapCorrupted(None),
+            "stack_overflow" => Self::StackOverflow(thread()),
+            "stack_underflow" => Self::StackUnderflow(thread()),
+            "bad_stack_pointer" => Self::BadStackPointer(crash.sp),
+            _ => None?,
+        })
+    }
+
+    /// Extra values explanations can use for this diagnosis, besides `{pc}`, `{lr}`, `{sp}` and
+    /// `{far}`
+    pub fn placeholders(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Self::SceneLoadingError(c) => vec![
+                (
+                    "{asset}",
+                    c.asset
+                        .map(|c| c.to_string())
+                        .unwrap_or("asset".to_string()),
+                ),
+                (
+                    "{slot}",
+                    c.slot
+                        .map(|c| c.to_string())
+                        .unwrap_or("unknown".to_string()),
+                ),
+            ],
+            Self::StackOverflow(c) | Self::StackUnderflow(c) => vec![("{thread}", c.clone())],
+            Self::AllocationFailed(c) | Self::HeapCorrupted(c) => {
+                vec![("{heap}", c.clone().unwrap_or("unknown".to_string()))]
+            }
+            Self::InterworkingMismatch { target, thumb } => vec![
+                ("{target}", format!("{target:08x}")),
+                ("{state}", if *thumb { "Thumb" } else { "ARM" }.to_string()),
+                ("{code}", if *thumb { "ARM" } else { "Thumb" }.to_string()),
+            ],
+            _ => vec![],
         }
     }
+}
+
+/// How likely a diagnosis is to be right, written like `100%` or `~80%`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Confidence {
+    pub percent: u8,
+    /// Whether the percentage is just a rough estimate
+    pub approximate: bool,
+}
+
+impl FromStr for Confidence {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (approximate, s) = match s.strip_prefix('~') {
+            Some(c) => (true, c),
+            None => (false, s),
+        };
+        let percent = s
+            .strip_suffix('%')
+            .and_then(|c| c.trim().parse::<u8>().ok())
+            .filter(|c| *c <= 100)
+            .ok_or(anyhow!(
+                "invalid confidence {s} (should be like 100% or ~80%)"
+            ))?;
+        Ok(Self {
+            percent,
+            approximate,
+        })
+    }
+}
+
+impl TryFrom<String> for Confidence {
+    type Error = anyhow::Error;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for Confidence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}%",
+            if self.approximate { "~" } else { "" },
+            self.percent
+        )
+    }
+}
+
+/// User-facing text for a diagnosis, so every front-end can show it the same way
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Explanation {
+    pub title: String,
+    pub confidence: Option<Confidence>,
+    /// Anything that doesn't fit as a cause or a fix
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub causes: Vec<String>,
+    #[serde(default)]
+    pub fixes: Vec<String>,
+    /// Names of related FAQ questions
+    #[serde(default)]
+    pub faq: Vec<String>,
+}
 
-    pub fn find_matches(crash: &CrashInfo) -> anyhow::Result<Vec<Self>> {
-        let region = crash.region();
-        let mut out = vec![];
+impl Explanation {
+    /// Keys of the explanation in a solver rule
+    pub const FIELDS: &[&str] = &["title", "confidence", "notes", "causes", "fixes", "faq"];
 
-        let Some(bounds) = super::analyze::get_megamix_bounds()?
+    /// Copy of the explanation with `{pc}`, `{lr}`, `{sp}`, `{far}` and the diagnosis' own placeholders
+    /// replaced with the values from the crash
+    pub fn filled(&self, crash: &CrashInfo, kind: &SolveDiagnosis) -> Self {
+        let placeholders = kind.placeholders();
+        let fill = |text: &String| {
+            let mut text = text.clone();
+            for (key, value) in &placeholders {
+                text = text.replace(key, value);
+            }
+            text.replace("{pc}", &format!("{:08x}", crash.pc))
+                .replace("{lr}", &format!("{:08x}", crash.lr))
+                .replace(
+                    "{sp}",
+                    &crash
+                        .sp
+                        .map(|c| format!("{c:08x}"))
+                        .unwrap_or("unknown".to_string()),
+                )
+                .replace(
+                    "{far}",
+                    &crash
+                        .far
+                        .map(|c| format!("{c:08x}"))
+                        .unwrap_or("unknown".to_string()),
+                )
+        };
+        Self {
+            title: fill(&self.title),
+            confidence: self.confidence,
+            notes: self.notes.as_ref().map(fill),
+            causes: self.causes.iter().map(fill).collect(),
+            fixes: self.fixes.iter().map(fill).collect(),
+            faq: self.faq.clone(),
+        }
+    }
+
+    pub fn faq_questions(&self) -> impl Iterator<Item = &'static FaqQuestion> {
+        self.faq
             .iter()
-            .find(|c| region.matches(&c.version))
-            .cloned()
-        else {
-            Err(anyhow!("Bounds file doesn't include {:?} region", region))?
+            .filter_map(|name| QUESTIONS.iter().find(|q| q.name == name))
+    }
+
+    /// Notes, causes, fixes and related FAQ questions, one per line. Each front-end refers to FAQ
+    /// questions its own way, so `see_also` gives what to show for each.
+    pub fn body(&self, see_also: impl Fn(&FaqQuestion) -> String) -> String {
+        let mut out = String::new();
+        if let Some(notes) = &self.notes {
+            out += &format!("{notes}\n");
+        }
+        if !self.causes.is_empty() {
+            out += "Things that might cause this:\n";
+            for cause in &self.causes {
+                out += &format!("- {cause}\n");
+            }
+        }
+        if !self.fixes.is_empty() {
+            out += "How to fix it:\n";
+            for fix in &self.fixes {
+                out += &format!("- {fix}\n");
+            }
+        }
+        for question in self.faq_questions() {
+            out += &format!("See also: {}\n", see_also(question));
+        }
+        out
+    }
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.title)?;
+        if let Some(confidence) = self.confidence {
+            write!(f, " ({confidence} chance)")?;
+        }
+        writeln!(f)?;
+        write!(f, "{}", self.body(|c| c.question.to_string()))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnosis {
+    pub kind: SolveDiagnosis,
+    pub explanation: Explanation,
+}
+
+/// Everything the solver found out about a crash
+#[derive(Clone, Debug, Default)]
+pub struct Solution {
+    /// Thread the crash happened in, if there's an SP and thread stacks to check it against
+    pub stack: Option<StackStatus>,
+    pub diagnoses: Vec<Diagnosis>,
+}
+
+impl Diagnosis {
+    /// For invalid Tickflow addresses, finds the operations in the crash's Tickflow binary that
+    /// jumped there, and narrows the causes down to what went wrong with them
+    pub fn pinpoint_tickflow(&mut self, crash: &CrashInfo, file_name: &str, bin: &TickflowBin) {
+        let SolveDiagnosis::InvalidTickflowAddress { culprits, .. } = &mut self.kind else {
+            return;
         };
+        *culprits = TickflowCulprit::find(crash, bin);
 
-        if region == Region::UNK {
-            panic!("Cannot solve for an UNK-region crash")
+        let mut notes = self
+            .explanation
+            .notes
+            .take()
+            .map(|c| c + "\n")
+            .unwrap_or_default();
+        if culprits.is_empty() {
+            notes += &format!("Couldn't find the operation that jumped there in {file_name}.");
+        } else {
+            notes += &format!("Jumped there from {file_name}:");
+            for culprit in culprits.iter() {
+                notes += &format!("\n- {culprit}");
+            }
+            let mut causes: Vec<String> = vec![];
+            for cause in culprits.iter().map(|c| c.cause().to_string()) {
+                if !causes.contains(&cause) {
+                    causes.push(cause);
+                }
+            }
+            self.explanation.causes = causes;
         }
+        self.explanation.notes = Some(notes);
+    }
 
-        if crash.pc == Self::invalid_tickflow_address_pc(region) {
-            out.push(Self::InvalidTickflowAddress(crash.far))
-        } else if crash.pc == Self::no_effect_memory_pc(region) {
-            out.push(Self::NoEffectMemory)
-        } else if crash.pc >= bounds.rodata
-            && (matches!(crash.engine, ModdingEngine::RHMPatch)
-                || (crash.pc < 0x07000000 || crash.pc >= 0x08000000))
-        {
-            out.push(Self::NonExecRegion(crash.pc))
+    pub fn no_region_data(region: Region) -> Self {
+        Self {
+            kind: SolveDiagnosis::NoRegionData(region),
+            explanation: Explanation {
+                title: match region {
+                    Region::UNK => {
+                        "Couldn't tell which region this copy of Megamix is from".to_string()
+                    }
+                    _ => format!("No solver data for copies of Megamix from {region}"),
+                },
+                notes: Some(
+                    "Bertram doesn't have the symbols it needs to solve crashes from this region, so no known crashes could be checked."
+                        .to_string(),
+                ),
+                faq: vec!["intl".to_string()],
+                ..Default::default()
+            },
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util,
+        tickflow::{Annotation, IndexEntry, C00_BASE},
+    };
 
-        /*if crash
-            .call_stack
-            .as_ref()
-            .is_some_and(|c| c.contains(&Self::scene_loading_lr(region)))
-        {*/
-        if crash.pc == Self::forbidden_layout_pc(region) {
-            out.push(Self::SceneLoadingError(SceneLoadDiagnosis::LowSlotLayout))
-            //    } else {
-            //        out.push(Self::SceneLoadingError(SceneLoadDiagnosis::Generic))
+    fn op(offset: u32, opcode: u16, arg0: u32, args: &[u32]) -> Operation {
+        Operation {
+            offset,
+            opcode,
+            arg0,
+            args: args.to_vec(),
         }
-        //}
+    }
 
-        //keep this in last
-        if out.is_empty() && crash.far.map_or(false, |c| c < 0x00100000) {
-            out.push(Self::NullRead)
+    fn bin(kind: BinKind, ops: Vec<Operation>, annotations: Vec<Annotation>) -> TickflowBin {
+        let code_end = ops.last().map(|c| c.offset + c.size()).unwrap_or(12);
+        TickflowBin {
+            kind,
+            code_start: 12,
+            code_end,
+            strings_start: code_end,
+            ops,
+            annotations,
+            data: vec![],
+            size: code_end,
         }
+    }
+
+    /// C00.bin with a call by sub number, a sub by location, and a valid call in between
+    fn c00() -> TickflowBin {
+        let entry = IndexEntry {
+            index: 0x1,
+            start: C00_BASE + 16,
+            assets: C00_BASE + 16,
+        };
+        bin(
+            BinKind::C00(vec![entry]),
+            vec![
+                op(16, OP_CALL, 0, &[0x5]),
+                op(24, OP_CALL, 0, &[C00_BASE + 16]),
+                op(32, OP_SUB, 0, &[C00_BASE + 24]),
+                op(40, OP_RETURN, 0, &[]),
+            ],
+            vec![],
+        )
+    }
+
+    fn offsets(culprits: &[TickflowCulprit]) -> Vec<(u32, CulpritReason)> {
+        culprits.iter().map(|c| (c.op.offset, c.reason)).collect()
+    }
 
-        Ok(out)
+    #[test]
+    fn finds_culprit_by_target() {
+        let crash = CrashInfo {
+            far: Some(0x5),
+            ..test_util::crash()
+        };
+        let culprits = TickflowCulprit::find(&crash, &c00());
+        assert_eq!(offsets(&culprits), [(16, CulpritReason::Target)]);
+        assert_eq!(culprits[0].to_string(), "`call 0x5` at offset 0x10");
+    }
+
+    #[test]
+    fn finds_culprit_by_register() {
+        let mut r = [0; 13];
+        // the interpreter has already moved past the sub
+        r[4] = C00_BASE + 40;
+        let crash = CrashInfo {
+            far: Some(0x1234),
+            r: Some(r),
+            ..test_util::crash()
+        };
+        let culprits = TickflowCulprit::find(&crash, &c00());
+        assert_eq!(offsets(&culprits), [(32, CulpritReason::Register(4))]);
+        assert_eq!(
+            culprits[0].to_string(),
+            format!(
+                "`sub 0x{:x}` at offset 0x20 (r4 points to it)",
+                C00_BASE + 24
+            )
+        );
+    }
+
+    #[test]
+    fn finds_suspicious_culprits() {
+        let crash = CrashInfo {
+            far: Some(0x1234),
+            ..test_util::crash()
+        };
+        let culprits = TickflowCulprit::find(&crash, &c00());
+        assert_eq!(
+            offsets(&culprits),
+            [
+                (16, CulpritReason::Suspicious),
+                (32, CulpritReason::Suspicious)
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_suspicious_culprits_in_tickompiler_bin() {
+        let entry = IndexEntry {
+            index: 0x1,
+            start: 0,
+            assets: 0,
+        };
+        let ops = vec![op(12, OP_CALL, 0, &[0x5]), op(20, OP_CALL, 0, &[0x0])];
+        let annotations = vec![Annotation {
+            offset: 24,
+            kind: ArgKind::Location,
+        }];
+        let tickompiler = bin(BinKind::Single(entry), ops.clone(), annotations);
+
+        // the bin could be anywhere, so registers don't point to anything in it
+        let mut r = [0; 13];
+        r[0] = 20;
+        let crash = CrashInfo {
+            far: Some(0x1234),
+            r: Some(r),
+            ..test_util::crash()
+        };
+        let culprits = TickflowCulprit::find(&crash, &tickompiler);
+        assert_eq!(offsets(&culprits), [(12, CulpritReason::Suspicious)]);
+
+        // without annotations, nothing tells locations apart
+        let unannotated = bin(BinKind::Single(entry), ops, vec![]);
+        assert!(TickflowCulprit::find(&crash, &unannotated).is_empty());
+    }
+
+    #[test]
+    fn shows_explanation() {
+        let explanation = Explanation {
+            title: "Ran out of memory".to_string(),
+            confidence: Some("~80%".parse().unwrap()),
+            notes: Some("The heap is full.".to_string()),
+            causes: vec!["Big files".to_string()],
+            fixes: vec!["Smaller files".to_string()],
+            faq: vec!["intl".to_string()],
+        };
+        let body = "The heap is full.\n\
+             Things that might cause this:\n- Big files\n\
+             How to fix it:\n- Smaller files\n";
+        assert_eq!(
+            explanation.body(|c| format!("`!faq {}`", c.name)),
+            format!("{body}See also: `!faq intl`\n")
+        );
+        assert_eq!(
+            explanation.to_string(),
+            format!(
+                "Ran out of memory (~80% chance)\n{body}See also: {}\n",
+                QUESTIONS
+                    .iter()
+                    .find(|c| c.name == "intl")
+                    .unwrap()
+                    .question
+            )
+        );
+    }
+
+    #[test]
+    fn points_out_culprits_in_notes() {
+        let crash = CrashInfo {
+            far: Some(0x5),
+            ..test_util::crash()
+        };
+        let mut diagnosis = Diagnosis {
+            kind: SolveDiagnosis::InvalidTickflowAddress {
+                far: crash.far,
+                culprits: vec![],
+            },
+            explanation: Explanation {
+                notes: Some("Tickflow jumped to 0x5.".to_string()),
+                ..Default::default()
+            },
+        };
+        diagnosis.pinpoint_tickflow(&crash, "C00.bin", &c00());
+        assert_eq!(
+            diagnosis.explanation.notes.as_deref(),
+            Some(
+                "Tickflow jumped to 0x5.\nJumped there from C00.bin:\n- `call 0x5` at offset 0x10"
+            )
+        );
+        assert_eq!(
+            diagnosis.explanation.causes,
+            [TickflowCulprit {
+                op: op(16, OP_CALL, 0, &[0x5]),
+                reason: CulpritReason::Target,
+            }
+            .cause()]
+        );
     }
 }
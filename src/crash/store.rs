@@ -0,0 +1,561 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::crash::{
+    analyze::{get_megamix_bounds, get_symbol_table, CsvBounds, Symbols, CTRPLUGIN_END_SYMBOLS},
+    dwarf::DebugInfo,
+    saltwater::{Region, SWDVersion},
+    ModdingEngine,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+struct ManifestFile {
+    file: String,
+    sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ManifestRegion {
+    region: String,
+    file: String,
+    sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ManifestSaltwater {
+    version: String,
+    file: String,
+    sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Manifest {
+    bounds: ManifestFile,
+    #[serde(default)]
+    region: Vec<ManifestRegion>,
+    #[serde(default)]
+    saltwater: Vec<ManifestSaltwater>,
+    /// Saltwater debug commits (by their 7-digit short hash), mapped to the release they're based on
+    #[serde(default)]
+    commits: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum SymbolKind {
+    Bounds,
+    Megamix(Region),
+    Saltwater(SWDVersion),
+    SaltwaterDebugInfo(String),
+}
+
+impl Display for SymbolKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bounds => write!(f, "Megamix bounds"),
+            Self::Megamix(region) => write!(f, "Megamix ({region})"),
+            Self::Saltwater(version) => write!(f, "Saltwater {version}"),
+            Self::SaltwaterDebugInfo(hash) => write!(f, "Saltwater commit {hash} (debug info)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StoreEntry {
+    pub kind: SymbolKind,
+    pub path: PathBuf,
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyStatus {
+    Ok,
+    /// The file exists, but there's no checksum to compare it against
+    Unchecked,
+    Missing,
+    Mismatch {
+        expected: String,
+        found: String,
+    },
+}
+
+impl Display for VerifyStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ok => write!(f, "OK"),
+            Self::Unchecked => write!(f, "present (no checksum)"),
+            Self::Missing => write!(f, "missing"),
+            Self::Mismatch { expected, found } => {
+                write!(f, "checksum mismatch (expected {expected}, found {found})")
+            }
+        }
+    }
+}
+
+/// Folder containing every symbol file, described by its `manifest.toml`
+#[derive(Debug, Clone)]
+pub struct SymbolStore {
+    root: PathBuf,
+    manifest: Manifest,
+}
+
+impl SymbolStore {
+    pub const DEFAULT_ROOT: &str = "sym";
+    pub const ROOT_ENV_VAR: &str = "BERTRAM_SYMBOLS";
+    pub const MANIFEST_NAME: &str = "manifest.toml";
+    /// Length of the commit hashes in the manifest's `[commits]`, which is all a crash dump has
+    pub const COMMIT_HASH_LEN: usize = 7;
+
+    pub fn new(root: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let root = root.into();
+        let manifest_path = root.join(Self::MANIFEST_NAME);
+        let manifest: Manifest = match fs::read_to_string(&manifest_path) {
+            Ok(c) => toml::from_str(&c)
+                .map_err(|e| anyhow!("Invalid symbol manifest {}: {e}", manifest_path.display()))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Err(anyhow!(
+                "Symbol manifest not found (expected {})",
+                manifest_path.display()
+            ))?,
+            Err(e) => Err(e)?,
+        };
+        if let Some(hash) = manifest.commits.keys().find(|c| {
+            c.len() != Self::COMMIT_HASH_LEN
+                || !c
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+        }) {
+            Err(anyhow!(
+                "Invalid symbol manifest {}: commit `{hash}` should be a short hash of {} lowercase hex digits",
+                manifest_path.display(),
+                Self::COMMIT_HASH_LEN
+            ))?
+        }
+        Ok(Self { root, manifest })
+    }
+
+    /// Opens the store at the folder in `BERTRAM_SYMBOLS`, or `sym` if it's not set
+    pub fn from_env() -> anyhow::Result<Self> {
+        Self::new(std::env::var(Self::ROOT_ENV_VAR).unwrap_or(Self::DEFAULT_ROOT.to_string()))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn existing(&self, file: &str, what: impl Display) -> anyhow::Result<PathBuf> {
+        let path = self.root.join(file);
+        if path.is_file() {
+            Ok(path)
+        } else {
+            Err(anyhow!(
+                "Missing symbols for {what}: {} doesn't exist",
+                path.display()
+            ))
+        }
+    }
+
+    pub fn bounds_path(&self) -> anyhow::Result<PathBuf> {
+        self.existing(&self.manifest.bounds.file, SymbolKind::Bounds)
+    }
+
+    pub fn bounds(&self) -> anyhow::Result<Vec<CsvBounds>> {
+        get_megamix_bounds(self.bounds_path()?)
+    }
+
+    pub fn region_bounds(&self, region: Region) -> anyhow::Result<CsvBounds> {
+        self.bounds()?
+            .into_iter()
+            .find(|c| region.matches(&c.version))
+            .ok_or(anyhow!("Bounds file doesn't include {:?} region", region))
+    }
+
+    pub fn megamix_path(&self, region: Region) -> anyhow::Result<PathBuf> {
+        let Some(entry) = self
+            .manifest
+            .region
+            .iter()
+            .find(|c| region.matches(&c.region))
+        else {
+            Err(anyhow!(
+                "The symbol manifest doesn't list Megamix symbols for the {region} region"
+            ))?
+        };
+        self.existing(&entry.file, SymbolKind::Megamix(region))
+    }
+
+    /// Path to Saltwater debug symbols, which aren't part of the manifest
+    pub fn saltwater_debug_path(&self, commit_hash: &str) -> PathBuf {
+        self.root.join(format!("sw._{commit_hash}.csv"))
+    }
+
+    /// Path to the ELF of a Saltwater debug build, used for source lines
+    pub fn saltwater_elf_path(&self, commit_hash: &str) -> PathBuf {
+        self.root.join(format!("sw._{commit_hash}.elf"))
+    }
+
+    /// Opens the debug info for the plugin in a crash, if it's a Saltwater debug build and its ELF
+    /// is in the store
+    pub fn debug_info(&self, engine: &ModdingEngine) -> anyhow::Result<Option<DebugInfo>> {
+        let ModdingEngine::SpiceRack(_, SWDVersion::Debug { commit_hash }, _) = engine else {
+            return Ok(None);
+        };
+        let path = self.saltwater_elf_path(commit_hash);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Ok(Some(DebugInfo::open(path)?))
+    }
+
+    pub fn saltwater_path(&self, version: &SWDVersion) -> anyhow::Result<PathBuf> {
+        match version {
+            SWDVersion::Debug { commit_hash } => {
+                let path = self.saltwater_debug_path(commit_hash);
+                if path.is_file() {
+                    Ok(path)
+                } else {
+                    Err(anyhow!(
+                        "Missing symbols for Saltwater {version}: {} doesn't exist (generate it with symbolgen)",
+                        path.display()
+                    ))
+                }
+            }
+            SWDVersion::Release { .. } => {
+                let Some(entry) = self
+                    .saltwater_entries()
+                    .find(|(v, _)| v == version)
+                    .map(|c| c.1)
+                else {
+                    Err(anyhow!(
+                        "The symbol manifest doesn't list symbols for Saltwater {version}"
+                    ))?
+                };
+                self.existing(&entry.file, SymbolKind::Saltwater(version.clone()))
+            }
+        }
+    }
+
+    fn saltwater_entries(&self) -> impl Iterator<Item = (SWDVersion, &ManifestSaltwater)> {
+        self.manifest
+            .saltwater
+            .iter()
+            .filter_map(|c| Some((c.version.parse().ok()?, c)))
+    }
+
+    /// Release a Saltwater debug commit is based on, according to the manifest. Longer hashes are
+    /// looked up by their short hash.
+    pub fn commit_release(&self, commit_hash: &str) -> Option<SWDVersion> {
+        self.manifest
+            .commits
+            .get(commit_hash.get(..Self::COMMIT_HASH_LEN)?)?
+            .parse()
+            .ok()
+    }
+
+    /// Releases that have symbols available, sorted from oldest to newest
+    pub fn available_releases(&self) -> Vec<SWDVersion> {
+        let mut out = self
+            .saltwater_entries()
+            .filter(|(_, entry)| self.root.join(&entry.file).is_file())
+            .map(|c| c.0)
+            .collect::<Vec<_>>();
+        out.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        out
+    }
+
+    /// Finds the symbols closest to the given Saltwater version: the version itself if possible,
+    /// otherwise the newest release before it, otherwise the oldest release after it.
+    /// Debug commits without symbols only fall back to a release if they're listed in the
+    /// manifest's `[commits]` table, since there's no way to tell which release they're close to.
+    /// Returns the version that was picked, or `None` if there are no fitting symbols.
+    pub fn nearest_saltwater(&self, version: &SWDVersion) -> Option<SWDVersion> {
+        if self.saltwater_path(version).is_ok() {
+            return Some(version.clone());
+        }
+        let releases = self.available_releases();
+        let target = match version {
+            SWDVersion::Debug { commit_hash } => self.commit_release(commit_hash)?,
+            SWDVersion::Release { .. } => version.clone(),
+        };
+        releases
+            .iter()
+            .rev()
+            .find(|c| **c <= target)
+            .or(releases.first())
+            .cloned()
+    }
+
+    /// Opens the symbols needed to analyze a crash from the given engine. If there are no symbols
+    /// for the exact Saltwater version, the closest one is used and a warning is added.
+    pub fn symbols(&self, engine: &ModdingEngine) -> anyhow::Result<Symbols> {
+        match engine {
+            ModdingEngine::RHMPatch => self.megamix_symbols(engine.region()),
+            ModdingEngine::SpiceRack(_, version, region) => {
+                let nearest = self.nearest_saltwater(version);
+                let mut symbols = self.open_symbols(*region, nearest.as_ref())?;
+                match nearest {
+                    Some(c) if c == *version => {}
+                    Some(c) => symbols.warn(format!(
+                        "No symbols for Saltwater {version}, using the ones for Saltwater {c} instead. Plugin symbols might be wrong!"
+                    )),
+                    None if matches!(version, SWDVersion::Debug { .. }) => symbols.warn(format!(
+                        "No symbols for Saltwater {version}, and it isn't listed in the symbol manifest's [commits] table, only Megamix symbols are shown"
+                    )),
+                    None => symbols.warn(format!(
+                        "No symbols for Saltwater {version} or any other version, only Megamix symbols are shown"
+                    )),
+                }
+                Ok(symbols)
+            }
+        }
+    }
+
+    /// Opens the symbols for Megamix on its own, without any plugin
+    pub fn megamix_symbols(&self, region: Region) -> anyhow::Result<Symbols> {
+        self.open_symbols(region, None)
+    }
+
+    fn open_symbols(
+        &self,
+        region: Region,
+        version: Option<&SWDVersion>,
+    ) -> anyhow::Result<Symbols> {
+        if region == Region::UNK {
+            Err(anyhow!("Cannot analyze unknown region"))?
+        }
+        let mut symbols = Symbols::from_paths(
+            self.megamix_path(region)?,
+            match version {
+                Some(c) => self.saltwater_path(c)?,
+                None => PathBuf::new(),
+            },
+        )?;
+        symbols.init_bounds(self.region_bounds(region)?)?;
+        Ok(symbols)
+    }
+
+    /// Every symbol file the store knows about, including Saltwater debug symbols and ELFs found in
+    /// the folder
+    pub fn list(&self) -> Vec<StoreEntry> {
+        let mut out = vec![StoreEntry {
+            kind: SymbolKind::Bounds,
+            path: self.root.join(&self.manifest.bounds.file),
+            sha256: self.manifest.bounds.sha256.clone(),
+        }];
+        for entry in &self.manifest.region {
+            let Ok(region) = entry.region.parse() else {
+                continue;
+            };
+            out.push(StoreEntry {
+                kind: SymbolKind::Megamix(region),
+                path: self.root.join(&entry.file),
+                sha256: entry.sha256.clone(),
+            });
+        }
+        for (version, entry) in self.saltwater_entries() {
+            out.push(StoreEntry {
+                kind: SymbolKind::Saltwater(version),
+                path: self.root.join(&entry.file),
+                sha256: entry.sha256.clone(),
+            });
+        }
+
+        let mut debug = fs::read_dir(&self.root)
+            .into_iter()
+            .flatten()
+            .filter_map(|c| {
+                let name = c.ok()?.file_name().into_string().ok()?;
+                let name_hash = name.strip_prefix("sw._")?;
+                let kind = if let Some(hash) = name_hash.strip_suffix(".csv") {
+                    SymbolKind::Saltwater(SWDVersion::Debug {
+                        commit_hash: hash.to_string(),
+                    })
+                } else {
+                    SymbolKind::SaltwaterDebugInfo(name_hash.strip_suffix(".elf")?.to_string())
+                };
+                Some(StoreEntry {
+                    kind,
+                    path: self.root.join(&name),
+                    sha256: None,
+                })
+            })
+            .collect::<Vec<_>>();
+        debug.sort_by(|a, b| a.path.cmp(&b.path));
+        out.extend(debug);
+        out
+    }
+
+    /// Problems with the Saltwater symbol files that affect every crash analyzed with them, to be
+    /// shown once when the store is opened
+    pub fn warnings(&self) -> Vec<String> {
+        self.list()
+            .into_iter()
+            .filter(|c| matches!(c.kind, SymbolKind::Saltwater(_)) && c.path.is_file())
+            .filter_map(|entry| {
+                let table = match get_symbol_table(&entry.path) {
+                    Ok(c) => c,
+                    Err(e) => {
+                        return Some(format!("Couldn't read the {} symbols: {e}", entry.kind))
+                    }
+                };
+                let has_end = table
+                    .iter()
+                    .any(|c| CTRPLUGIN_END_SYMBOLS.contains(&c.full_name().as_str()));
+                (!has_end).then(|| {
+                    format!(
+                        "{} symbols don't contain any of {}, so plugin variables won't be named",
+                        entry.kind,
+                        CTRPLUGIN_END_SYMBOLS.join(", ")
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Checks that every file in the store exists and matches its checksum
+    pub fn verify(&self) -> anyhow::Result<Vec<(StoreEntry, VerifyStatus)>> {
+        self.list()
+            .into_iter()
+            .map(|entry| {
+                let status = if !entry.path.is_file() {
+                    VerifyStatus::Missing
+                } else if let Some(expected) = &entry.sha256 {
+                    let mut hasher = Sha256::new();
+                    io::copy(&mut File::open(&entry.path)?, &mut hasher)?;
+                    let found = format!("{:x}", hasher.finalize());
+                    if found.eq_ignore_ascii_case(expected) {
+                        VerifyStatus::Ok
+                    } else {
+                        VerifyStatus::Mismatch {
+                            expected: expected.clone(),
+                            found,
+                        }
+                    }
+                } else {
+                    VerifyStatus::Unchecked
+                };
+                Ok((entry, status))
+            })
+            .try_collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    /// Store with symbols for Saltwater 0.2 and 0.4, and 0.3 listed but missing
+    fn store(name: &str) -> (TempDir, SymbolStore) {
+        let dir = TempDir::with_files(
+            name,
+            &[
+                (
+                    SymbolStore::MANIFEST_NAME,
+                    r#"
+                    bounds.file = "bounds.csv"
+                    saltwater = [
+                        { version = "0.4", file = "sw.0.4.csv" },
+                        { version = "0.3", file = "sw.0.3.csv" },
+                        { version = "0.2", file = "sw.0.2.csv" },
+                    ]
+                    [commits]
+                    "abc1234" = "0.3"
+                    "#,
+                ),
+                ("sw.0.2.csv", ""),
+                ("sw.0.4.csv", ""),
+                ("sw._def5678.csv", ""),
+            ],
+        );
+        let store = SymbolStore::new(dir.path()).unwrap();
+        (dir, store)
+    }
+
+    fn nearest(store: &SymbolStore, version: &str) -> Option<String> {
+        store
+            .nearest_saltwater(&version.parse().unwrap())
+            .map(|c| c.to_string())
+    }
+
+    #[test]
+    fn warns_about_saltwater_symbols_without_data_end() {
+        let (dir, store) = store("warnings");
+        dir.write("sw.0.4.csv", "name,location,namespace\n__end__,07100000,\n");
+        assert_eq!(
+            store.warnings(),
+            [
+                "Saltwater 0.2 symbols don't contain any of __end__, _end, __bss_end__, so plugin variables won't be named",
+                "Saltwater commit def5678 symbols don't contain any of __end__, _end, __bss_end__, so plugin variables won't be named",
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_available_releases_in_order() {
+        let (_dir, store) = store("releases");
+        let releases = store
+            .available_releases()
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(releases, ["0.2", "0.4"]);
+    }
+
+    #[test]
+    fn falls_back_to_nearest_release() {
+        let (_dir, store) = store("nearest");
+        assert_eq!(nearest(&store, "0.4").as_deref(), Some("0.4"));
+        assert_eq!(nearest(&store, "0.3").as_deref(), Some("0.2"));
+        assert_eq!(nearest(&store, "0.5").as_deref(), Some("0.4"));
+        assert_eq!(nearest(&store, "0.1").as_deref(), Some("0.2"));
+    }
+
+    #[test]
+    fn debug_commits_only_fall_back_if_listed() {
+        let (_dir, store) = store("commits");
+        assert_eq!(
+            nearest(&store, "def5678").as_deref(),
+            Some("commit def5678")
+        );
+        assert_eq!(nearest(&store, "abc1234").as_deref(), Some("0.2"));
+        assert_eq!(nearest(&store, "1234567"), None);
+    }
+
+    #[test]
+    fn looks_up_commits_by_short_hash() {
+        let (_dir, store) = store("short-hash");
+        assert_eq!(
+            store.commit_release("abc1234").map(|c| c.to_string()),
+            Some("0.3".to_string())
+        );
+        assert_eq!(
+            store
+                .commit_release("abc1234ffffffff")
+                .map(|c| c.to_string()),
+            Some("0.3".to_string())
+        );
+        assert_eq!(store.commit_release("abc123"), None);
+    }
+
+    #[test]
+    fn rejects_commits_that_arent_short_hashes() {
+        for hash in ["abc", "abc12345", "ABC1234", "xyz1234"] {
+            let dir = TempDir::with_files(
+                &format!("commit-{hash}"),
+                &[(
+                    SymbolStore::MANIFEST_NAME,
+                    &format!("bounds.file = \"bounds.csv\"\n[commits]\n\"{hash}\" = \"0.2\""),
+                )],
+            );
+            assert!(SymbolStore::new(dir.path()).is_err(), "{hash}");
+        }
+    }
+}
@@ -0,0 +1,491 @@
+use std::{
+    ffi::CString,
+    fmt::{self, Display},
+    io::{Read, Seek, SeekFrom},
+    ops::Range,
+};
+
+use anyhow::anyhow;
+use bytestream::{ByteOrder::LittleEndian as LE, StreamReader};
+
+/// Address CTRPF plugins get loaded at
+pub const PLUGIN_BASE: u32 = 0x07000000;
+/// Memory a CTRPF plugin's code and data can be in
+pub const PLUGIN_RANGE: Range<u32> = PLUGIN_BASE..0x08000000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    Console,
+    Citra,
+    ConsoleAndCitra,
+    Unknown(u8),
+}
+
+impl From<u8> for Compatibility {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Console,
+            1 => Self::Citra,
+            2 => Self::ConsoleAndCitra,
+            c => Self::Unknown(c),
+        }
+    }
+}
+
+impl Display for Compatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Console => write!(f, "Console only"),
+            Self::Citra => write!(f, "Citra only"),
+            Self::ConsoleAndCitra => write!(f, "Console and Citra"),
+            Self::Unknown(c) => write!(f, "Unknown ({c})"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginVersion {
+    pub major: u8,
+    pub minor: u8,
+    pub revision: u8,
+}
+
+impl From<u32> for PluginVersion {
+    fn from(value: u32) -> Self {
+        Self {
+            major: (value >> 24) as u8,
+            minor: (value >> 16) as u8,
+            revision: (value >> 8) as u8,
+        }
+    }
+}
+
+impl Display for PluginVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.revision)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PluginFlags {
+    pub embedded_exe_load_func: bool,
+    pub embedded_swap_save_load_func: bool,
+    /// Size of the memory region the plugin asks Luma for, in bytes
+    pub memory_region_size: Option<u32>,
+    pub compatibility: Compatibility,
+    pub events_self_managed: bool,
+    pub swap_not_needed: bool,
+    pub use_private_memory: bool,
+}
+
+impl From<u32> for PluginFlags {
+    fn from(value: u32) -> Self {
+        Self {
+            embedded_exe_load_func: value & 1 != 0,
+            embedded_swap_save_load_func: (value >> 1) & 1 != 0,
+            memory_region_size: match (value >> 2) & 3 {
+                0 => Some(5 * 1024 * 1024),
+                1 => Some(2 * 1024 * 1024),
+                2 => Some(10 * 1024 * 1024),
+                _ => None,
+            },
+            compatibility: Compatibility::from(((value >> 4) & 3) as u8),
+            events_self_managed: (value >> 6) & 1 != 0,
+            swap_not_needed: (value >> 7) & 1 != 0,
+            use_private_memory: (value >> 8) & 1 != 0,
+        }
+    }
+}
+
+/// Where each part of the plugin's code lives inside the 3GX file
+#[derive(Debug, Clone)]
+pub struct Executable {
+    pub code_offset: u32,
+    pub rodata_offset: u32,
+    pub data_offset: u32,
+    pub code_size: u32,
+    pub rodata_size: u32,
+    pub data_size: u32,
+    pub bss_size: u32,
+    /// Only present in 3GX$0002 onwards
+    pub exe_load_func_offset: Option<u32>,
+    pub swap_save_func_offset: Option<u32>,
+    pub swap_load_func_offset: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PluginSymbol {
+    pub address: u32,
+    pub size: u16,
+    pub flags: u16,
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Plugin {
+    /// Number in the magic, as in `3GX$XXXX`
+    pub format: u16,
+    pub version: PluginVersion,
+    pub title: String,
+    pub author: String,
+    pub summary: String,
+    pub description: String,
+    pub flags: PluginFlags,
+    pub exe_load_checksum: Option<u32>,
+    pub executable: Executable,
+    pub targets: Vec<u32>,
+    pub symbols: Vec<PluginSymbol>,
+}
+
+/*
+    ### 3GX header layout: ###
+    0x00  magic ("3GX$XXXX")       0x08  plugin version       0x0C  reserved
+    0x10  infos: author, title, summary, description (length + offset each), flags
+          (3GX$0002+) exe load checksum, built-in load exe args (x4), swap save/load args (x4)
+    ----  executable: code/rodata/data offsets, code/rodata/data/bss sizes
+          (3GX$0002+) exe load func, swap save func, swap load func offsets
+    ----  targets (count + offset), symbol table (count, symbols offset, name table offset)
+*/
+
+impl Plugin {
+    pub const MIN_FORMAT: u16 = 1;
+    pub const MAX_FORMAT: u16 = 2;
+
+    pub fn from_file(f: &mut (impl Read + Seek)) -> anyhow::Result<Self> {
+        let mut magic = [0u8; 8];
+        f.read_exact(&mut magic)?;
+        if &magic[..4] != b"3GX$" {
+            Err(anyhow!("Not a 3GX plugin"))?
+        }
+        let Some(format) = std::str::from_utf8(&magic[4..])
+            .ok()
+            .and_then(|c| c.parse::<u16>().ok())
+        else {
+            Err(anyhow!("Invalid 3GX version in magic"))?
+        };
+        if !(Self::MIN_FORMAT..=Self::MAX_FORMAT).contains(&format) {
+            Err(anyhow!("Unsupported 3GX version 3GX${format:04}"))?
+        }
+
+        let version = PluginVersion::from(u32::read_from(f, LE)?);
+        f.seek(SeekFrom::Current(4))?; // reserved
+
+        let mut strings = [(0, 0); 4];
+        for string in &mut strings {
+            *string = (u32::read_from(f, LE)?, u32::read_from(f, LE)?);
+        }
+        let flags = PluginFlags::from(u32::read_from(f, LE)?);
+        let exe_load_checksum = if format >= 2 {
+            let c = u32::read_from(f, LE)?;
+            f.seek(SeekFrom::Current(0x20))?; // built-in load args
+            Some(c)
+        } else {
+            None
+        };
+
+        let code_offset = u32::read_from(f, LE)?;
+        let rodata_offset = u32::read_from(f, LE)?;
+        let data_offset = u32::read_from(f, LE)?;
+        let code_size = u32::read_from(f, LE)?;
+        let rodata_size = u32::read_from(f, LE)?;
+        let data_size = u32::read_from(f, LE)?;
+        let bss_size = u32::read_from(f, LE)?;
+        let (exe_load_func_offset, swap_save_func_offset, swap_load_func_offset) = if format >= 2 {
+            (
+                Some(u32::read_from(f, LE)?),
+                Some(u32::read_from(f, LE)?),
+                Some(u32::read_from(f, LE)?),
+            )
+        } else {
+            (None, None, None)
+        };
+        let executable = Executable {
+            code_offset,
+            rodata_offset,
+            data_offset,
+            code_size,
+            rodata_size,
+            data_size,
+            bss_size,
+            exe_load_func_offset,
+            swap_save_func_offset,
+            swap_load_func_offset,
+        };
+
+        let num_targets = u32::read_from(f, LE)?;
+        let targets_offset = u32::read_from(f, LE)? as u64;
+        let num_symbols = u32::read_from(f, LE)? as u64;
+        let symbols_offset = u32::read_from(f, LE)? as u64;
+        let name_table = u32::read_from(f, LE)? as u64;
+
+        let [author, title, summary, description] = strings;
+        let author = read_string(f, author.1 as u64, author.0)?;
+        let title = read_string(f, title.1 as u64, title.0)?;
+        let summary = read_string(f, summary.1 as u64, summary.0)?;
+        let description = read_string(f, description.1 as u64, description.0)?;
+
+        f.seek(SeekFrom::Start(targets_offset))?;
+        let mut targets = vec![];
+        for _ in 0..num_targets {
+            targets.push(u32::read_from(f, LE)?);
+        }
+
+        let mut symbols = vec![];
+        for i in 0..num_symbols {
+            f.seek(SeekFrom::Start(symbols_offset + 0xC * i))?;
+
+            let address = u32::read_from(f, LE)?;
+            let size = u16::read_from(f, LE)?;
+            let flags = u16::read_from(f, LE)?;
+            let name_pos = u32::read_from(f, LE)? as u64;
+
+            f.seek(SeekFrom::Start(name_table + name_pos))?;
+            let mut name = vec![];
+            loop {
+                let c = u8::read_from(f, LE)?;
+                if c == 0 {
+                    break;
+                }
+                name.push(c);
+            }
+            let Ok(name) = CString::new(name)?.into_string() else {
+                Err(anyhow!("could not read symbol name"))?
+            };
+
+            symbols.push(PluginSymbol {
+                address,
+                size,
+                flags,
+                name,
+            });
+        }
+
+        f.seek(SeekFrom::Start(0))?;
+
+        Ok(Self {
+            format,
+            version,
+            title,
+            author,
+            summary,
+            description,
+            flags,
+            exe_load_checksum,
+            executable,
+            targets,
+            symbols,
+        })
+    }
+
+    /// Git revision the plugin was built from, as written by Saltwater's build in its
+    /// metadata (`rev XXXXXXX`).
+    ///
+    /// The 3GX format has no field for it and Saltwater doesn't export it as a symbol, so the
+    /// metadata text is the only place it can be read from. Only a whole `rev` word followed by
+    /// exactly 7 hex digits counts.
+    pub fn commit_hash(&self) -> Option<String> {
+        [&self.title, &self.summary, &self.description]
+            .iter()
+            .find_map(|c| {
+                let words = c
+                    .split(|c: char| !c.is_ascii_alphanumeric())
+                    .collect::<Vec<_>>();
+                words.windows(2).find_map(|c| match c {
+                    ["rev", hash]
+                        if hash.len() == 7
+                            && hash
+                                .chars()
+                                .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)) =>
+                    {
+                        Some(hash.to_string())
+                    }
+                    _ => None,
+                })
+            })
+    }
+}
+
+fn read_string(f: &mut (impl Read + Seek), offset: u64, len: u32) -> anyhow::Result<String> {
+    if len == 0 {
+        return Ok(String::new());
+    }
+    f.seek(SeekFrom::Start(offset))?;
+    let mut buf = vec![0u8; len as usize];
+    f.read_exact(&mut buf)?;
+    if let Some(end) = buf.iter().position(|c| *c == 0) {
+        buf.truncate(end);
+    }
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+impl Display for Plugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            concat!(
+                "{} v{} by {}\n",
+                "{}\n\n",
+                "Format: 3GX${:04}\n",
+                "Compatibility: {}\n",
+                "Targets: {}\n",
+                "Code: {:#x} bytes, rodata: {:#x} bytes, data: {:#x} bytes, bss: {:#x} bytes\n",
+                "Symbols: {}",
+            ),
+            self.title,
+            self.version,
+            self.author,
+            self.summary,
+            self.format,
+            self.flags.compatibility,
+            self.format_targets(),
+            self.executable.code_size,
+            self.executable.rodata_size,
+            self.executable.data_size,
+            self.executable.bss_size,
+            self.symbols.len(),
+        )
+    }
+}
+
+impl Plugin {
+    fn format_targets(&self) -> String {
+        if self.targets.is_empty() {
+            "any title".to_string()
+        } else {
+            self.targets
+                .iter()
+                .map(|c| format!("{:016X}", 0x0004000000000000 | *c as u64))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    }
+}
+
+#[cfg(feature = "bot")]
+use serenity::builder::CreateEmbed;
+
+#[cfg(feature = "bot")]
+impl Plugin {
+    pub fn as_serenity_embed(&self, embed: CreateEmbed) -> CreateEmbed {
+        let mut embed = embed
+            .title(format!("{} v{}", self.title, self.version))
+            .description(&self.summary)
+            .field("Author", &self.author, true)
+            .field("Format", format!("3GX${:04}", self.format), true)
+            .field("Compatibility", self.flags.compatibility.to_string(), true)
+            .field("Targets", self.format_targets(), false)
+            .field(
+                "Executable",
+                format!(
+                    "code: `{:#x}` bytes\nrodata: `{:#x}` bytes\ndata: `{:#x}` bytes\nbss: `{:#x}` bytes",
+                    self.executable.code_size,
+                    self.executable.rodata_size,
+                    self.executable.data_size,
+                    self.executable.bss_size,
+                ),
+                true,
+            )
+            .field("Symbols", self.symbols.len().to_string(), true);
+        if let Some(c) = self.commit_hash() {
+            embed = embed.field("Commit", format!("`{c}`"), true);
+        }
+        if !self.description.is_empty() {
+            embed = embed.field(
+                "Description",
+                self.description.chars().take(1024).collect::<String>(),
+                false,
+            );
+        }
+        embed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// A 3GX$0001 plugin with one target and two symbols, and the given summary
+    fn plugin_file(summary: &str) -> Vec<u8> {
+        let strings = ["someone", "Saltwater", summary, ""];
+        let mut out = b"3GX$0001".to_vec();
+        out.extend(0x01020300u32.to_le_bytes());
+        out.extend([0; 4]);
+        let mut text: Vec<u8> = vec![];
+        let text_start = 0x64 + 4 + 2 * 0xC;
+        for string in strings {
+            out.extend((string.len() as u32).to_le_bytes());
+            out.extend((text_start + text.len() as u32).to_le_bytes());
+            text.extend(string.as_bytes());
+        }
+        out.extend((2u32 << 4).to_le_bytes());
+        for c in [0x100, 0x200, 0x300, 0x1000, 0x100, 0x80, 0x40] {
+            out.extend(u32::to_le_bytes(c));
+        }
+        let names = text_start + text.len() as u32;
+        for c in [1, 0x64, 2, 0x68, names] {
+            out.extend(u32::to_le_bytes(c));
+        }
+        assert_eq!(out.len(), 0x64);
+        out.extend(0x00155a00u32.to_le_bytes());
+        for (address, name_pos) in [(PLUGIN_BASE, 0u32), (PLUGIN_BASE + 0x10, 5)] {
+            out.extend(address.to_le_bytes());
+            out.extend(0x10u16.to_le_bytes());
+            out.extend(0u16.to_le_bytes());
+            out.extend(name_pos.to_le_bytes());
+        }
+        out.extend(text);
+        out.extend(b"main\0loop\0");
+        out
+    }
+
+    #[test]
+    fn reads_header_and_symbols() {
+        let plugin = Plugin::from_file(&mut Cursor::new(plugin_file("A mod"))).unwrap();
+        assert_eq!(plugin.format, 1);
+        assert_eq!(plugin.version.to_string(), "1.2.3");
+        assert_eq!(plugin.author, "someone");
+        assert_eq!(plugin.title, "Saltwater");
+        assert_eq!(plugin.summary, "A mod");
+        assert_eq!(plugin.description, "");
+        assert_eq!(plugin.flags.compatibility, Compatibility::ConsoleAndCitra);
+        assert_eq!(plugin.exe_load_checksum, None);
+        assert_eq!(plugin.executable.code_size, 0x1000);
+        assert_eq!(plugin.executable.bss_size, 0x40);
+        assert_eq!(plugin.targets, [0x00155a00]);
+        let symbols = plugin
+            .symbols
+            .iter()
+            .map(|c| (c.address, c.name.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            symbols,
+            [(PLUGIN_BASE, "main"), (PLUGIN_BASE + 0x10, "loop")]
+        );
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let mut file = plugin_file("");
+        assert!(Plugin::from_file(&mut Cursor::new(&file[4..])).is_err());
+        file[4..8].copy_from_slice(b"0003");
+        assert!(Plugin::from_file(&mut Cursor::new(file)).is_err());
+    }
+
+    #[test]
+    fn commit_hash_needs_rev_and_7_hex_digits() {
+        let hash = |summary| {
+            Plugin::from_file(&mut Cursor::new(plugin_file(summary)))
+                .unwrap()
+                .commit_hash()
+        };
+        assert_eq!(
+            hash("Debug build (rev 1a2b3c4)"),
+            Some("1a2b3c4".to_string())
+        );
+        assert_eq!(hash("Debug build (rev 1a2b3c4d)"), None);
+        assert_eq!(hash("Debug build (rev 1A2B3C4)"), None);
+        assert_eq!(hash("Debug build (prev 1a2b3c4)"), None);
+    }
+}
@@ -1,5 +1,11 @@
 #![feature(iterator_try_collect)]
 
+pub mod assets;
 pub mod crash;
+pub mod ctrplugin;
 pub mod ctru;
 pub mod faq;
+pub mod tickflow;
+
+#[cfg(test)]
+mod test_util;
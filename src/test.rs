@@ -9,14 +9,23 @@ use std::{
 use bertram::{
     crash::{
         analyze::{self, CrashAnalysis, Symbols},
+        diff::SymbolDiff,
         luma::CrashLuma,
-        saltwater::CrashSWD,
+        saltwater::{CrashSWD, SWDVersion},
         solve::SolveDiagnosis,
+        store::SymbolStore,
     },
     ctru::CtruError,
 };
 
 fn main() -> anyhow::Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let [command, old, new] = args.as_slice()
+        && command == "symdiff"
+    {
+        return symdiff(old, new);
+    }
+
     //println!("{}", CtruError::from_code(0xd8c3fbf3));
     //println!("{}", CtruError::from_code(0xc8804478));
 
@@ -40,3 +49,23 @@ fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Prints the symbols that changed between two Saltwater versions (or debug commit hashes), like
+/// the bot's symdiff command: `bertram-test symdiff 0.2 0.3`
+fn symdiff(old: &str, new: &str) -> anyhow::Result<()> {
+    let store = SymbolStore::from_env()?;
+    let old: SWDVersion = old.parse()?;
+    let new: SWDVersion = new.parse()?;
+    let diff = SymbolDiff::between(
+        &analyze::get_symbol_table(store.saltwater_path(&old)?)?,
+        &analyze::get_symbol_table(store.saltwater_path(&new)?)?,
+    );
+
+    println!("Saltwater {old} → {new}");
+    if diff.is_empty() {
+        println!("No symbols changed");
+    } else {
+        print!("{diff}");
+    }
+    Ok(())
+}
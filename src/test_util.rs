@@ -0,0 +1,146 @@
+// Fixtures shared by the unit tests
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    assets::romfs::RomfsLayout,
+    crash::{analyze::CsvBounds, CrashInfo, ExcType, ModdingEngine},
+};
+
+/// Folder in the system's temp folder for a test's files, removed when it's dropped
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates an empty folder. `name` has to be different for every test, since they run in
+    /// parallel.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("bertram-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    /// Creates a folder with the given files, as (path in the folder, contents)
+    pub fn with_files(name: &str, files: &[(&str, &str)]) -> Self {
+        let out = Self::new(name);
+        for (path, contents) in files {
+            out.write(path, contents);
+        }
+        out
+    }
+
+    /// Writes a file, creating the folders it's in
+    pub fn write(&self, path: &str, contents: &str) {
+        let path = self.0.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Files of a database with one game in two versions and one remix with that game in it
+pub const ASSET_FILES: &[(&str, &str)] = &[
+    ("file.list", "# games\nfrog.toml\n\nremix.toml\n"),
+    (
+        RomfsLayout::FILE_NAME,
+        r#"
+        [regions]
+        US = "US"
+
+        [paths]
+        cellanim = "cellanim/{name}.zlib"
+        layout = "{region}layout/{name}.zlib"
+        effect = "effect/{name}.zlib"
+        model = "model/{name}.zlib"
+        aac = "stream/{name}.bcstm"
+        "#,
+    ),
+    (
+        "frog.toml",
+        r#"
+        name = "Frog Hop"
+        scene = 0x14
+        keywords = ["frog hop", "frog"]
+        effect = "ntrFrog"
+        sfx = "GROUP_FROG"
+
+        [versions]
+        long = "Frog Hop"
+        arrange = "Frog Hop 2"
+        remix = ["remix00"]
+
+        [indexes]
+        long = 0x1
+        arrange = 0x2
+
+        [prologue]
+        long = "ntrFrog"
+        arrange = "ntrFrog2"
+
+        [cellanim]
+        long = "ntrFrog_long"
+        "#,
+    ),
+    (
+        "remix.toml",
+        r#"
+        remix = true
+        name = "Honeybee Remix"
+        index = 0x60
+        keywords = ["remix00"]
+
+        [prologue]
+        long = "remix00"
+
+        [[game]]
+        scene = 0x14
+        cellanim = "ntrFrog_remix00"
+        sfx = "GROUP_FROG"
+        "#,
+    ),
+];
+
+/// Bounds of the US version of Megamix, as in sym/bounds.csv
+pub fn bounds() -> CsvBounds {
+    CsvBounds {
+        version: "US".to_string(),
+        code: 0x00100000,
+        rodata: 0x0039A000,
+        data: 0x00521000,
+        bss_offset: 0x0054E074,
+        bss_size: 0x0008D27C,
+    }
+}
+
+/// Data abort in the US version with RHMPatch, with every register zeroed
+pub fn crash() -> CrashInfo {
+    CrashInfo {
+        engine: ModdingEngine::RHMPatch,
+        exception_type: ExcType::DataAbort,
+        r: Some([0; 13]),
+        sp: None,
+        lr: 0,
+        pc: 0,
+        cpsr: 0x10,
+        dfsr: None,
+        ifsr: None,
+        far: None,
+        fpexc: None,
+        fpinst: None,
+        fpinst2: None,
+        stack: None,
+        call_stack: None,
+    }
+}
@@ -0,0 +1,223 @@
+// Problems that come up when merging the Tickflow of several mods into one C00.bin
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{
+    assets::{AssetDatabase, IndexName},
+    tickflow::{BinKind, TickflowBin},
+};
+
+/// A mod's compiled Tickflow, along with the name to refer to it by (usually its file name)
+#[derive(Debug, Clone)]
+pub struct ModTickflow {
+    pub name: String,
+    pub bin: TickflowBin,
+}
+
+impl ModTickflow {
+    /// Indexes the mod replaces, in the order they're listed
+    pub fn replaced(&self, assets: Option<&AssetDatabase>) -> Vec<NamedIndex> {
+        self.bin
+            .entries()
+            .iter()
+            .map(|c| NamedIndex::new(c.index, assets))
+            .collect()
+    }
+}
+
+/// A game index and what it's used for, if it's in the asset database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedIndex {
+    pub index: u32,
+    pub name: Option<IndexName>,
+}
+
+impl NamedIndex {
+    pub fn new(index: u32, assets: Option<&AssetDatabase>) -> Self {
+        Self {
+            index,
+            name: assets.and_then(|c| c.index_name(index)),
+        }
+    }
+}
+
+impl Display for NamedIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "0x{:x}", self.index)?;
+        match &self.name {
+            Some(c) => write!(f, " - {c}"),
+            None => write!(f, " - unknown index"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// More than one mod replaces the same index
+    SharedIndex {
+        index: NamedIndex,
+        mods: Vec<String>,
+    },
+    /// The same index is listed more than once in a mod, so only one of them can be used
+    RepeatedIndex { index: NamedIndex, mod_name: String },
+    /// The mod is a C00.bin, so its Tickflow has already been placed at C00's address and can't
+    /// be merged with anything else
+    Linked { mod_name: String },
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SharedIndex { index, mods } => {
+                write!(f, "{index} is replaced by {}", mods.join(", "))
+            }
+            Self::RepeatedIndex { index, mod_name } => {
+                write!(f, "{index} is listed more than once in {mod_name}")
+            }
+            Self::Linked { mod_name } => write!(
+                f,
+                "{mod_name} has already been merged into a C00.bin, so it has to be merged again from the .bin files it came from"
+            ),
+        }
+    }
+}
+
+impl Conflict {
+    /// Checks whether the mods can be merged into one C00.bin. Conflicts between mods are listed
+    /// first, sorted by index.
+    pub fn find(mods: &[ModTickflow], assets: Option<&AssetDatabase>) -> Vec<Self> {
+        let mut users: BTreeMap<u32, Vec<&str>> = BTreeMap::new();
+        let mut repeated = vec![];
+        for tickflow in mods {
+            let mut seen = vec![];
+            for entry in tickflow.bin.entries() {
+                if seen.contains(&entry.index) {
+                    if !repeated.contains(&(entry.index, &tickflow.name)) {
+                        repeated.push((entry.index, &tickflow.name));
+                    }
+                    continue;
+                }
+                seen.push(entry.index);
+                users.entry(entry.index).or_default().push(&tickflow.name);
+            }
+        }
+
+        let mut out = users
+            .into_iter()
+            .filter(|(_, c)| c.len() > 1)
+            .map(|(index, mods)| Self::SharedIndex {
+                index: NamedIndex::new(index, assets),
+                mods: mods.into_iter().map(String::from).collect(),
+            })
+            .collect::<Vec<_>>();
+        out.extend(
+            repeated
+                .into_iter()
+                .map(|(index, mod_name)| Self::RepeatedIndex {
+                    index: NamedIndex::new(index, assets),
+                    mod_name: mod_name.clone(),
+                }),
+        );
+        if mods.len() > 1 {
+            out.extend(
+                mods.iter()
+                    .filter(|c| matches!(c.bin.kind, BinKind::C00(_)))
+                    .map(|c| Self::Linked {
+                        mod_name: c.name.clone(),
+                    }),
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tickflow::IndexEntry;
+
+    fn tickflow(name: &str, kind: BinKind) -> ModTickflow {
+        ModTickflow {
+            name: name.to_string(),
+            bin: TickflowBin {
+                kind,
+                code_start: 0,
+                code_end: 0,
+                strings_start: 0,
+                ops: vec![],
+                annotations: vec![],
+                data: vec![],
+                size: 0,
+            },
+        }
+    }
+
+    fn entry(index: u32) -> IndexEntry {
+        IndexEntry {
+            index,
+            start: 0,
+            assets: 0,
+        }
+    }
+
+    fn index(index: u32) -> NamedIndex {
+        NamedIndex::new(index, None)
+    }
+
+    #[test]
+    fn finds_indexes_replaced_by_several_mods() {
+        let mods = [
+            tickflow("a.bin", BinKind::Single(entry(0x2))),
+            tickflow("b.bin", BinKind::Single(entry(0x1))),
+            tickflow("c.bin", BinKind::Single(entry(0x2))),
+        ];
+        let conflicts = Conflict::find(&mods, None);
+        assert_eq!(
+            conflicts,
+            [Conflict::SharedIndex {
+                index: index(0x2),
+                mods: vec!["a.bin".to_string(), "c.bin".to_string()],
+            }]
+        );
+        assert_eq!(
+            conflicts[0].to_string(),
+            "0x2 - unknown index is replaced by a.bin, c.bin"
+        );
+    }
+
+    #[test]
+    fn finds_indexes_repeated_in_one_mod() {
+        let mods = [tickflow(
+            "C00.bin",
+            BinKind::C00(vec![entry(0x1), entry(0x2), entry(0x1), entry(0x1)]),
+        )];
+        // a C00.bin on its own doesn't need merging
+        assert_eq!(
+            Conflict::find(&mods, None),
+            [Conflict::RepeatedIndex {
+                index: index(0x1),
+                mod_name: "C00.bin".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn c00_cant_be_merged_with_other_mods() {
+        let mods = [
+            tickflow("C00.bin", BinKind::C00(vec![entry(0x1), entry(0x3)])),
+            tickflow("a.bin", BinKind::Single(entry(0x1))),
+        ];
+        assert_eq!(
+            Conflict::find(&mods, None),
+            [
+                Conflict::SharedIndex {
+                    index: index(0x1),
+                    mods: vec!["C00.bin".to_string(), "a.bin".to_string()],
+                },
+                Conflict::Linked {
+                    mod_name: "C00.bin".to_string(),
+                },
+            ]
+        );
+    }
+}
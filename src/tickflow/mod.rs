@@ -0,0 +1,585 @@
+// Tickflow binaries, as made by Tickompiler (.bin, one game each) and merged for RHMPatch (C00.bin)
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    io::{Cursor, Read, Seek},
+};
+
+use anyhow::anyhow;
+use bytestream::{ByteOrder::LittleEndian as LE, StreamReader};
+
+use crate::assets::AssetDatabase;
+
+pub mod conflict;
+
+/// Address RHMPatch loads C00.bin at
+pub const C00_BASE: u32 = 0x0C000000;
+
+/// Marks the end of the code in a Tickompiler .bin
+const END_OF_CODE: u32 = 0xFFFFFFFE;
+/// Marks the end of the index table in a C00.bin
+const END_OF_TABLE: u32 = 0xFFFFFFFF;
+
+pub const OP_ASYNC_CALL: u16 = 0x2;
+pub const OP_SUB: u16 = 0x4;
+pub const OP_CALL: u16 = 0x6;
+pub const OP_RETURN: u16 = 0x7;
+pub const OP_LABEL: u16 = 0x15;
+pub const OP_GOTO: u16 = 0x16;
+
+pub const OPERATION_NAMES: &[(u16, &str)] = &[
+    (OP_ASYNC_CALL, "async_call"),
+    (OP_SUB, "sub"),
+    (OP_CALL, "call"),
+    (OP_RETURN, "return"),
+    (OP_LABEL, "label"),
+    (OP_GOTO, "goto"),
+];
+
+/// Entry of the index table, telling the game where the Tickflow for a game index starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub index: u32,
+    /// Sub that runs the game
+    pub start: u32,
+    /// Sub that loads the game's assets
+    pub assets: u32,
+}
+
+#[derive(Debug, Clone)]
+pub enum BinKind {
+    /// Tickompiler output for a single game, with its subs as offsets into the file
+    Single(IndexEntry),
+    /// Merged binary for RHMPatch, with its subs as addresses in memory
+    C00(Vec<IndexEntry>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operation {
+    /// Offset of the operation in the file
+    pub offset: u32,
+    pub opcode: u16,
+    pub arg0: u32,
+    pub args: Vec<u32>,
+}
+
+impl Operation {
+    /// Reads an operation. The op word is laid out as `arg0 (18 bits) | argc (4) | opcode (10)`.
+    fn read_from<F: Read>(f: &mut F, offset: u32, word: u32) -> anyhow::Result<Self> {
+        let argc = (word >> 10) & 0xF;
+        let mut args = vec![];
+        for _ in 0..argc {
+            args.push(u32::read_from(f, LE)?);
+        }
+        Ok(Self {
+            offset,
+            opcode: (word & 0x3FF) as u16,
+            arg0: word >> 14,
+            args,
+        })
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        OPERATION_NAMES
+            .iter()
+            .find(|(k, _)| *k == self.opcode)
+            .map(|c| c.1)
+    }
+
+    /// Size of the operation in bytes
+    pub fn size(&self) -> u32 {
+        4 + 4 * self.args.len() as u32
+    }
+
+    /// Whether the operation makes Tickflow continue somewhere else
+    pub fn is_jump(&self) -> bool {
+        matches!(
+            self.opcode,
+            OP_ASYNC_CALL | OP_SUB | OP_CALL | OP_RETURN | OP_GOTO
+        )
+    }
+
+    /// Whether the operation jumps to `target` (as a location, sub or label number), going by its
+    /// arguments. `return` doesn't have a target of its own, so it never does.
+    pub fn jumps_to(&self, target: u32) -> bool {
+        self.is_jump()
+            && self.opcode != OP_RETURN
+            && (self.arg0 == target || self.args.first() == Some(&target))
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(c) => write!(f, "{c}")?,
+            None => write!(f, "0x{:x}", self.opcode)?,
+        }
+        if self.arg0 != 0 {
+            write!(f, "<0x{:x}>", self.arg0)?;
+        }
+        for (i, arg) in self.args.iter().enumerate() {
+            write!(f, "{}0x{arg:x}", if i == 0 { " " } else { ", " })?;
+        }
+        Ok(())
+    }
+}
+
+/// What an argument of an operation points to, as marked by Tickompiler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    Location,
+    String,
+    UnicodeString,
+}
+
+impl ArgKind {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Location),
+            1 => Some(Self::String),
+            2 => Some(Self::UnicodeString),
+            _ => None,
+        }
+    }
+}
+
+/// Argument that isn't a plain number, from the list after the code of a Tickompiler .bin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Annotation {
+    /// Offset of the argument in the file
+    pub offset: u32,
+    pub kind: ArgKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct TickflowBin {
+    pub kind: BinKind,
+    /// Offset of the first operation. Locations in a Tickompiler .bin are relative to it.
+    pub code_start: u32,
+    /// Offset right after the last operation
+    pub code_end: u32,
+    /// Offset of the strings of a Tickompiler .bin. When it's loaded they're placed right after
+    /// the code, so that's where its locations expect them to be.
+    pub strings_start: u32,
+    pub ops: Vec<Operation>,
+    /// Only Tickompiler .bin files have these, C00.bin files have already been linked
+    pub annotations: Vec<Annotation>,
+    pub data: Vec<u8>,
+    /// Size of the file in bytes
+    pub size: u32,
+}
+
+impl TickflowBin {
+    /// Reads a Tickompiler .bin or a C00.bin, telling them apart by whether the subs in the
+    /// header are addresses in C00's memory.
+    ///
+    /// A Tickompiler .bin is laid out as: index, start and assets subs, code, `0xFFFFFFFE`,
+    /// annotations (`offset << 8 | kind`, with the offset from the start of the code),
+    /// `0xFFFFFFFF` and the strings. A C00.bin is a table of indexes and subs ending with
+    /// `0xFFFFFFFF`, followed by the code and strings of every game in it. Since nothing marks
+    /// where its code ends, the strings its operations point to are skipped, and reading only
+    /// goes on past them if there's more known code (a sub from the table or a call target) after.
+    pub fn from_file<F: Read + Seek>(f: &mut F) -> anyhow::Result<Self> {
+        f.rewind()?;
+        let mut data = vec![];
+        f.read_to_end(&mut data)?;
+        let size = data.len() as u32;
+        let mut f = Cursor::new(data.as_slice());
+
+        let mut entries = vec![];
+        loop {
+            let index = u32::read_from(&mut f, LE)?;
+            if index == END_OF_TABLE {
+                break;
+            }
+            entries.push(IndexEntry {
+                index,
+                start: u32::read_from(&mut f, LE)?,
+                assets: u32::read_from(&mut f, LE)?,
+            });
+            if entries[0].start < C00_BASE {
+                break;
+            }
+        }
+        let kind = match entries.as_slice() {
+            [] => Err(anyhow!("Not a Tickflow binary (the index table is empty)"))?,
+            [entry] if entry.start < C00_BASE => BinKind::Single(*entry),
+            _ => BinKind::C00(entries),
+        };
+        let code_start = f.position() as u32;
+
+        let mut ops = vec![];
+        let mut ended = false;
+        // only used for C00.bin, as offsets in the file
+        let mut code = match &kind {
+            BinKind::C00(c) => c
+                .iter()
+                .flat_map(|c| [c.start, c.assets])
+                .filter_map(|c| c.checked_sub(C00_BASE))
+                .collect::<Vec<_>>(),
+            BinKind::Single(_) => vec![],
+        };
+        let mut strings = BTreeMap::new();
+        loop {
+            let mut offset = f.position() as u32;
+            // skip the strings after the code of a game in a C00.bin
+            while !code.contains(&offset)
+                && let Some((_, end)) = strings.range(..=offset).next_back()
+                && *end > offset
+            {
+                offset = *end;
+            }
+            if offset != f.position() as u32 {
+                if !code.iter().any(|c| *c >= offset) {
+                    break;
+                }
+                f.set_position(offset as u64);
+            }
+            let Ok(word) = u32::read_from(&mut f, LE) else {
+                break;
+            };
+            if word == END_OF_CODE {
+                ended = true;
+                break;
+            }
+            let Ok(op) = Operation::read_from(&mut f, offset, word) else {
+                break;
+            };
+            if let BinKind::C00(_) = kind {
+                for (i, arg) in op.args.iter().enumerate() {
+                    let Some(target) = arg
+                        .checked_sub(C00_BASE)
+                        .filter(|c| *c > offset && *c < size)
+                    else {
+                        continue;
+                    };
+                    if i == 0 && matches!(op.opcode, OP_CALL | OP_ASYNC_CALL) {
+                        code.push(target);
+                    } else if !code.contains(&target) {
+                        strings.insert(target, string_end(&data, target));
+                    }
+                }
+            }
+            ops.push(op);
+        }
+
+        let code_end = ops.last().map_or(code_start, |c| c.offset + c.size());
+
+        let mut annotations = vec![];
+        if ended && let BinKind::Single(_) = kind {
+            while let Ok(word) = u32::read_from(&mut f, LE)
+                && word != END_OF_TABLE
+            {
+                let offset = code_start + (word >> 8);
+                match ArgKind::from_u8(word as u8) {
+                    Some(kind) if offset < size => annotations.push(Annotation { offset, kind }),
+                    // not what Tickompiler writes, so don't trust any of it
+                    _ => {
+                        annotations.clear();
+                        break;
+                    }
+                }
+            }
+        }
+
+        let strings_start = if annotations.is_empty() {
+            code_end
+        } else {
+            f.position() as u32
+        };
+
+        Ok(Self {
+            kind,
+            code_start,
+            code_end,
+            strings_start,
+            ops,
+            annotations,
+            data,
+            size,
+        })
+    }
+
+    /// Every game index the file has Tickflow for, in the order they're listed
+    pub fn entries(&self) -> &[IndexEntry] {
+        match &self.kind {
+            BinKind::Single(c) => std::slice::from_ref(c),
+            BinKind::C00(c) => c,
+        }
+    }
+
+    /// Address the file gets loaded at, if it's always the same
+    pub fn base(&self) -> Option<u32> {
+        match self.kind {
+            BinKind::C00(_) => Some(C00_BASE),
+            BinKind::Single(_) => None,
+        }
+    }
+
+    /// Operation at the given address in memory, or right before it (the interpreter might have
+    /// already moved past it). If only one of them is a jump, that one is picked.
+    pub fn op_near(&self, address: u32) -> Option<&Operation> {
+        let offset = address.checked_sub(self.base()?)?;
+        if offset >= self.size {
+            return None;
+        }
+        let mut near = self
+            .ops
+            .iter()
+            .filter(|c| c.offset == offset || c.offset + c.size() == offset);
+        near.clone().find(|c| c.is_jump()).or_else(|| near.next())
+    }
+
+    /// Whether `address` points into the file once it's loaded
+    pub fn contains(&self, address: u32) -> bool {
+        self.base()
+            .is_some_and(|c| address >= c && address - c < self.size)
+    }
+
+    /// Offset in the file a location points to - an address in memory for C00.bin, or relative to
+    /// the start of the code for a Tickompiler .bin
+    pub fn file_offset(&self, location: u32) -> Option<u32> {
+        let offset = match self.kind {
+            BinKind::C00(_) => location.checked_sub(C00_BASE)?,
+            BinKind::Single(_) => {
+                let code_size = self.code_end - self.code_start;
+                match location.checked_sub(code_size) {
+                    Some(c) => self.strings_start.checked_add(c)?,
+                    None => self.code_start + location,
+                }
+            }
+        };
+        (offset < self.size).then_some(offset)
+    }
+
+    /// Reads the string an argument points to
+    pub fn string_at(&self, location: u32, kind: ArgKind) -> Option<String> {
+        let data = &self.data[self.file_offset(location)? as usize..];
+        match kind {
+            ArgKind::Location => None,
+            ArgKind::String => {
+                let end = data.iter().position(|c| *c == 0)?;
+                Some(String::from_utf8_lossy(&data[..end]).into_owned())
+            }
+            ArgKind::UnicodeString => {
+                let chars = data
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .take_while(|c| *c != 0)
+                    .collect::<Vec<_>>();
+                Some(String::from_utf16_lossy(&chars))
+            }
+        }
+    }
+
+    /// Kind of the `i`th argument of `op`. Without annotations, the first argument of a call is
+    /// taken as a location if it points into the file.
+    pub fn arg_kind(&self, op: &Operation, i: usize) -> Option<ArgKind> {
+        let offset = op.offset + 4 + 4 * i as u32;
+        if let Some(c) = self.annotations.iter().find(|c| c.offset == offset) {
+            return Some(c.kind);
+        }
+        (self.annotations.is_empty()
+            && i == 0
+            && matches!(op.opcode, OP_CALL | OP_ASYNC_CALL)
+            && self.file_offset(op.args[0]).is_some())
+        .then_some(ArgKind::Location)
+    }
+
+    /// Subs in the index table, as (name, offset in the file)
+    fn entry_labels(&self) -> Vec<(String, u32)> {
+        let mut out = vec![];
+        for entry in self.entries() {
+            let suffix = match self.kind {
+                BinKind::Single(_) => String::new(),
+                BinKind::C00(_) => format!("_{:x}", entry.index),
+            };
+            for (name, location) in [("start", entry.start), ("assets", entry.assets)] {
+                if let Some(offset) = self.file_offset(location) {
+                    out.push((format!("{name}{suffix}"), offset));
+                }
+            }
+        }
+        out
+    }
+
+    /// Turns the binary back into Tickompiler source, naming each game index with `assets`
+    pub fn disassemble(&self, assets: Option<&AssetDatabase>) -> String {
+        let describe = |index: u32| {
+            assets
+                .and_then(|c| c.index_name(index))
+                .map(|c| format!(" ; {c}"))
+                .unwrap_or_default()
+        };
+
+        let entry_labels = self.entry_labels();
+        let mut by_offset = entry_labels
+            .iter()
+            .map(|(name, offset)| (*offset, name.clone()))
+            .collect::<BTreeMap<_, _>>();
+        for op in &self.ops {
+            for (i, arg) in op.args.iter().enumerate() {
+                // locations that don't land on an operation are left as numbers
+                if self.arg_kind(op, i) == Some(ArgKind::Location)
+                    && let Some(offset) = self.file_offset(*arg)
+                    && self.ops.iter().any(|c| c.offset == offset)
+                {
+                    by_offset
+                        .entry(offset)
+                        .or_insert_with(|| format!("loc_{offset:x}"));
+                }
+            }
+        }
+
+        let mut out = String::new();
+        match &self.kind {
+            BinKind::Single(entry) => {
+                out += &format!("#index 0x{:x}{}\n", entry.index, describe(entry.index));
+                for (name, offset) in &entry_labels {
+                    out += &format!("#{name} {}\n", by_offset[offset]);
+                }
+            }
+            BinKind::C00(entries) => {
+                out += &format!("; C00.bin with {} games\n", entries.len());
+                for entry in entries {
+                    out += &format!(
+                        "; index 0x{:x}: start 0x{:08x}, assets 0x{:08x}{}\n",
+                        entry.index,
+                        entry.start,
+                        entry.assets,
+                        describe(entry.index)
+                    );
+                }
+            }
+        }
+
+        for op in &self.ops {
+            if let Some(label) = by_offset.get(&op.offset) {
+                out += &format!("\n{label}:\n");
+            }
+            out += "    ";
+            match op.name() {
+                Some(c) => out += c,
+                None => out += &format!("0x{:x}", op.opcode),
+            }
+            if op.arg0 != 0 {
+                out += &format!("<0x{:x}>", op.arg0);
+            }
+            for (i, arg) in op.args.iter().enumerate() {
+                out += if i == 0 { " " } else { ", " };
+                let kind = self.arg_kind(op, i);
+                out += &match kind {
+                    Some(ArgKind::Location) => self
+                        .file_offset(*arg)
+                        .and_then(|c| by_offset.get(&c).cloned())
+                        .unwrap_or(format!("0x{arg:x}")),
+                    Some(ArgKind::String) => match self.string_at(*arg, ArgKind::String) {
+                        Some(c) => format!("{c:?}"),
+                        None => format!("0x{arg:x}"),
+                    },
+                    Some(ArgKind::UnicodeString) => {
+                        match self.string_at(*arg, ArgKind::UnicodeString) {
+                            Some(c) => format!("u{c:?}"),
+                            None => format!("0x{arg:x}"),
+                        }
+                    }
+                    None => format!("0x{arg:x}"),
+                };
+            }
+            out += "\n";
+        }
+        out
+    }
+}
+
+/// Offset right after the string at `offset` in a C00.bin, rounded up to a word. A string that
+/// starts with a character and a zero byte is taken as UTF-16.
+fn string_end(data: &[u8], offset: u32) -> u32 {
+    let rest = &data[offset as usize..];
+    let len = if rest.len() >= 2 && rest[0] != 0 && rest[1] == 0 {
+        rest.chunks_exact(2)
+            .position(|c| c == [0, 0])
+            .map_or(rest.len(), |c| 2 * c + 2)
+    } else {
+        rest.iter()
+            .position(|c| *c == 0)
+            .map_or(rest.len(), |c| c + 1)
+    };
+    (offset + len as u32).next_multiple_of(4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(opcode: u32, arg0: u32, args: &[u32]) -> Vec<u8> {
+        let mut out = (opcode | (args.len() as u32) << 10 | arg0 << 14)
+            .to_le_bytes()
+            .to_vec();
+        for arg in args {
+            out.extend(arg.to_le_bytes());
+        }
+        out
+    }
+
+    fn words(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|c| c.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn disassembles_tickompiler_bin() {
+        let mut file = words(&[0x1, 0x0, 0x10]);
+        file.extend(op(OP_CALL as u32, 0, &[0x10]));
+        // the string comes right after the code, which is 0x18 bytes
+        file.extend(op(0x31, 0, &[0x18]));
+        file.extend(op(0x28, 3, &[]));
+        file.extend(op(OP_RETURN as u32, 0, &[]));
+        file.extend(words(&[END_OF_CODE, 0x4 << 8, 0xC << 8 | 1, END_OF_TABLE]));
+        file.extend(b"hi\0\0");
+
+        let bin = TickflowBin::from_file(&mut Cursor::new(file)).unwrap();
+        assert!(matches!(
+            bin.kind,
+            BinKind::Single(IndexEntry { index: 1, .. })
+        ));
+        assert_eq!(bin.annotations.len(), 2);
+        assert_eq!(
+            bin.disassemble(None),
+            "#index 0x1\n#start start\n#assets assets\n\n\
+             start:\n    call assets\n    0x31 \"hi\"\n\n\
+             assets:\n    0x28<0x3>\n    return\n"
+        );
+    }
+
+    #[test]
+    fn skips_strings_in_c00() {
+        let mut file = words(&[0x1, C00_BASE + 28, C00_BASE + 44]);
+        file.extend(words(&[0x2, C00_BASE + 56, C00_BASE + 64, END_OF_TABLE]));
+        file.extend(op(0x31, 0, &[C00_BASE + 48]));
+        file.extend(op(OP_CALL as u32, 0, &[C00_BASE + 44]));
+        file.extend(op(OP_RETURN as u32, 0, &[]));
+        file.extend(b"hello\0\0\0");
+        file.extend(op(0x32, 0, &[C00_BASE + 68]));
+        file.extend(op(OP_RETURN as u32, 0, &[]));
+        file.extend(b"h\0i\0\0\0\0\0");
+
+        let bin = TickflowBin::from_file(&mut Cursor::new(file)).unwrap();
+        let offsets = bin.ops.iter().map(|c| c.offset).collect::<Vec<_>>();
+        assert_eq!(offsets, [28, 36, 44, 56, 64]);
+        assert_eq!(bin.entries().len(), 2);
+        assert_eq!(bin.op_near(C00_BASE + 56).unwrap().opcode, 0x32);
+    }
+
+    #[test]
+    fn finds_string_ends() {
+        assert_eq!(string_end(b"hello\0\0\0", 0), 8);
+        assert_eq!(string_end(b"abc\0", 0), 4);
+        assert_eq!(string_end(b"h\0i\0\0\0\0\0", 0), 8);
+    }
+
+    #[test]
+    fn rejects_empty_table() {
+        assert!(TickflowBin::from_file(&mut Cursor::new(words(&[END_OF_TABLE]))).is_err());
+    }
+}